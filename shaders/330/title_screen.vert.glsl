@@ -2,10 +2,16 @@
 
 in vec2 vp;
 in vec2 vt;
+in vec4 vc;
+in float vs;
 out vec2 st;
+out vec4 color;
+out float silhouette;
 
 
 void main() {
     st = vt;
+    color = vc;
+    silhouette = vs;
     gl_Position = vec4(vp, 0.0, 1.0);
 }
@@ -1,12 +1,18 @@
 #version 420 core
 
 in vec2 st;
+in vec4 color;
+in float silhouette;
 uniform sampler2D tex;
 uniform vec4 text_color;
 out vec4 frag_color;
 
 
+// Shadows and outlines are drawn as silhouettes of the glyphs, which take their
+// shape from the atlas but their color entirely from the vertex color.
 void main () {
-    frag_color =  text_color * texture(tex, st);
+    vec4 texel = texture(tex, st);
+    vec3 rgb = mix(texel.rgb, vec3(1.0), silhouette);
+    frag_color = color * text_color * vec4(rgb, texel.a);
 }
 
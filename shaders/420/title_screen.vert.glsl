@@ -2,10 +2,16 @@
 
 in layout (location = 0) vec2 vp;
 in layout (location = 1) vec2 vt;
+in layout (location = 2) vec4 vc;
+in layout (location = 3) float vs;
 out vec2 st;
+out vec4 color;
+out float silhouette;
 
 
 void main() {
     st = vt;
+    color = vc;
+    silhouette = vs;
     gl_Position = vec4(vp, 0.0, 1.0);
 }
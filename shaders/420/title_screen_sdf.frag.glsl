@@ -0,0 +1,39 @@
+#version 420 core
+
+in vec2 st;
+in vec4 color;
+in float silhouette;
+uniform sampler2D tex;
+uniform vec4 text_color;
+uniform vec4 outline_color;
+uniform float outline_width;
+uniform vec4 glow_color;
+uniform float glow_width;
+out vec4 frag_color;
+
+
+// The atlas stores a signed distance field in the alpha channel. A value of 0.5
+// lies on the edge of a glyph, larger values lie inside it, and smaller values
+// lie outside it. The outline and glow widths are measured in the same units.
+// Shadows and outlines drawn as extra copies of the text are silhouettes of the
+// outlined glyph in the vertex color, without any glow.
+void main () {
+    float dist = texture(tex, st).a;
+    float smoothing = fwidth(dist);
+
+    float fill = smoothstep(0.5 - smoothing, 0.5 + smoothing, dist);
+    float outline_edge = 0.5 - outline_width;
+    float outline = smoothstep(outline_edge - smoothing, outline_edge + smoothing, dist);
+    vec4 body = mix(outline_color, color * text_color, fill);
+    body.a *= outline;
+    if (silhouette > 0.5) {
+        frag_color = color * text_color * vec4(1.0, 1.0, 1.0, outline);
+        return;
+    }
+
+    float glow = 0.0;
+    if (glow_width > 0.0) {
+        glow = glow_color.a * smoothstep(outline_edge - glow_width, outline_edge, dist);
+    }
+    frag_color = vec4(mix(glow_color.rgb, body.rgb, body.a), max(body.a, glow));
+}
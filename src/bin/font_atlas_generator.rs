@@ -0,0 +1,520 @@
+//! Generate a font atlas from a TrueType or OpenType font.
+//!
+//! The generator rasterizes each glyph of the font into a cell of a square grid,
+//! and writes out the atlas image as a PNG file along with a JSON file in the format
+//! the demo loads with `font_atlas::load_file`.
+//!
+//! ```text
+//! cargo run --bin font_atlas_generator -- <FONT_FILE> <OUTPUT_NAME> [OPTIONS]
+//! ```
+//!
+//! This writes `<OUTPUT_NAME>.png` and `<OUTPUT_NAME>.json`. With `--sdf-spread`, the
+//! atlas stores a signed distance field instead of glyph coverage, so the text
+//! stays sharp at any scale.
+extern crate png;
+extern crate rusttype;
+extern crate serde;
+extern crate serde_json;
+extern crate log;
+
+#[macro_use]
+extern crate serde_derive;
+
+#[allow(dead_code)]
+#[path = "../font_atlas.rs"]
+mod font_atlas;
+
+use font_atlas::{Address, DistanceField, FontAtlas};
+use rusttype::{point, Font, GlyphId, Scale};
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::fs::File;
+use std::io::BufWriter;
+use std::process;
+
+
+const USAGE: &str = "\
+USAGE: font_atlas_generator <FONT_FILE> <OUTPUT_NAME> [OPTIONS]
+
+OPTIONS:
+    --cell-size <PIXELS>    The width and height of each glyph cell (default: 256).
+    --padding <PIXELS>      The empty border around each glyph inside its cell (default: 8).
+    --glyphs <STRING>       The characters to put in the atlas (default: printable ASCII).
+    --glyph-file <FILE>     Read the characters to put in the atlas from a UTF-8 file.
+    --fallback <CHAR>       The glyph to substitute for characters missing from the atlas.
+    --sdf-spread <PIXELS>   Generate a signed distance field atlas that saturates this far from each glyph edge.";
+
+const DEFAULT_CELL_SIZE: usize = 256;
+const DEFAULT_PADDING: usize = 8;
+
+
+struct Config {
+    font_file: String,
+    output_name: String,
+    cell_size: usize,
+    padding: usize,
+    glyphs: Vec<char>,
+    fallback_glyph: Option<char>,
+    sdf_spread: Option<f32>,
+}
+
+fn default_glyphs() -> Vec<char> {
+    (0x20_u8..0x7F_u8).map(|byte| byte as char).collect()
+}
+
+fn parse_args(args: &[String]) -> Result<Config, String> {
+    if args.len() < 2 {
+        return Err(String::from("Missing the font file or the output name."));
+    }
+
+    let mut config = Config {
+        font_file: args[0].clone(),
+        output_name: args[1].clone(),
+        cell_size: DEFAULT_CELL_SIZE,
+        padding: DEFAULT_PADDING,
+        glyphs: default_glyphs(),
+        fallback_glyph: None,
+        sdf_spread: None,
+    };
+
+    let mut i = 2;
+    while i < args.len() {
+        let value = match args.get(i + 1) {
+            Some(value) => value,
+            None => return Err(format!("Missing a value for the option {}.", args[i])),
+        };
+        match args[i].as_str() {
+            "--cell-size" => {
+                config.cell_size = value.parse().map_err(|_| format!("Invalid cell size: {}", value))?;
+            }
+            "--padding" => {
+                config.padding = value.parse().map_err(|_| format!("Invalid padding: {}", value))?;
+            }
+            "--glyphs" => {
+                config.glyphs = value.chars().collect();
+            }
+            "--glyph-file" => {
+                let contents = fs::read_to_string(value)
+                    .map_err(|_| format!("Could not read the glyph file: {}", value))?;
+                config.glyphs = contents.chars().filter(|ch| !ch.is_control()).collect();
+            }
+            "--fallback" => {
+                let mut chars = value.chars();
+                config.fallback_glyph = match (chars.next(), chars.next()) {
+                    (Some(ch), None) => Some(ch),
+                    _ => return Err(format!("The fallback glyph must be a single character: {}", value)),
+                };
+            }
+            "--sdf-spread" => {
+                let spread: f32 = value.parse().map_err(|_| format!("Invalid distance field spread: {}", value))?;
+                if spread <= 0.0 {
+                    return Err(format!("The distance field spread must be positive: {}", value));
+                }
+                config.sdf_spread = Some(spread);
+            }
+            option => return Err(format!("Unknown option: {}", option)),
+        }
+        i += 2;
+    }
+
+    config.glyphs.sort();
+    config.glyphs.dedup();
+    if config.glyphs.is_empty() {
+        return Err(String::from("The glyph set is empty."));
+    }
+    if 2 * config.padding >= config.cell_size {
+        return Err(format!(
+            "The padding {} leaves no room for glyphs in a {} pixel cell.", config.padding, config.cell_size
+        ));
+    }
+    if let Some(spread) = config.sdf_spread {
+        if spread > config.padding as f32 {
+            eprintln!(
+                "WARNING: The distance field spread {} is wider than the padding {}, so the fields of \
+                wide glyphs will be clipped at the cell edges.", spread, config.padding
+            );
+        }
+    }
+
+    Ok(config)
+}
+
+/// The rasterized atlas image in RGBA format. Each glyph is drawn in white with its
+/// coverage or distance field in the alpha channel, so the text shader can tint it 
+/// with any color.
+struct AtlasImage {
+    width: usize,
+    height: usize,
+    data: Vec<u8>,
+}
+
+impl AtlasImage {
+    fn new(width: usize, height: usize) -> AtlasImage {
+        AtlasImage {
+            width: width,
+            height: height,
+            data: vec![0; 4 * width * height],
+        }
+    }
+
+    fn set_alpha(&mut self, x: usize, y: usize, alpha: f32) {
+        let offset = 4 * (y * self.width + x);
+        let alpha = (255.0 * f32::min(f32::max(alpha, 0.0), 1.0)) as u8;
+        self.data[offset] = 255;
+        self.data[offset + 1] = 255;
+        self.data[offset + 2] = 255;
+        self.data[offset + 3] = alpha;
+    }
+}
+
+/// A value larger than any squared distance inside a cell.
+const FAR: f64 = 1e20;
+
+/// Compute the squared distance transform of a sampled one dimensional function
+/// using the lower envelope of parabolas, following Felzenszwalb and Huttenlocher,
+/// "Distance Transforms of Sampled Functions".
+fn distance_transform_1d(f: &[f64], d: &mut [f64], v: &mut [usize], z: &mut [f64]) {
+    let n = f.len();
+    let mut k = 0;
+    v[0] = 0;
+    z[0] = -FAR;
+    z[1] = FAR;
+    for q in 1..n {
+        let qf = q as f64;
+        loop {
+            let p = v[k];
+            let pf = p as f64;
+            let s = ((f[q] + qf * qf) - (f[p] + pf * pf)) / (2.0 * qf - 2.0 * pf);
+            if s <= z[k] {
+                k -= 1;
+            } else {
+                k += 1;
+                v[k] = q;
+                z[k] = s;
+                z[k + 1] = FAR;
+                break;
+            }
+        }
+    }
+
+    k = 0;
+    for q in 0..n {
+        let qf = q as f64;
+        while z[k + 1] < qf {
+            k += 1;
+        }
+        let pf = v[k] as f64;
+        d[q] = (qf - pf) * (qf - pf) + f[v[k]];
+    }
+}
+
+/// Compute the Euclidean distance from each pixel of a square grid to the nearest
+/// pixel where `is_feature` holds.
+fn distance_transform(size: usize, is_feature: &[bool]) -> Vec<f64> {
+    let mut grid: Vec<f64> = is_feature.iter().map(|&feature| if feature { 0.0 } else { FAR }).collect();
+    let mut f = vec![0.0; size];
+    let mut d = vec![0.0; size];
+    let mut v = vec![0; size];
+    let mut z = vec![0.0; size + 1];
+
+    for x in 0..size {
+        for y in 0..size {
+            f[y] = grid[y * size + x];
+        }
+        distance_transform_1d(&f, &mut d, &mut v, &mut z);
+        for y in 0..size {
+            grid[y * size + x] = d[y];
+        }
+    }
+    for y in 0..size {
+        f.copy_from_slice(&grid[y * size..(y + 1) * size]);
+        distance_transform_1d(&f, &mut d, &mut v, &mut z);
+        grid[y * size..(y + 1) * size].copy_from_slice(&d);
+    }
+
+    grid.iter().map(|&distance| distance.sqrt()).collect()
+}
+
+/// Convert the coverage of a glyph cell into a signed distance field. The field
+/// is 0.5 on the edge of the glyph, and falls off linearly to 0 outside and rises
+/// to 1 inside the glyph at a distance of `spread` pixels from the edge.
+fn coverage_to_distance_field(size: usize, coverage: &[f32], spread: f32) -> Vec<f32> {
+    let inside: Vec<bool> = coverage.iter().map(|&value| value >= 0.5).collect();
+    let outside: Vec<bool> = inside.iter().map(|&value| !value).collect();
+    let distance_outside = distance_transform(size, &inside);
+    let distance_inside = distance_transform(size, &outside);
+
+    (0..size * size).map(|i| {
+        let signed_distance = (distance_outside[i] - distance_inside[i]) as f32;
+        0.5 - signed_distance / (2.0 * spread)
+    }).collect()
+}
+
+/// Look up the kerning of every ordered pair of glyphs the font has, in units of the
+/// cell size. The font does not list which pairs it kerns, so this asks about all of
+/// them: the cost grows with the square of the number of glyphs, which is about nine
+/// thousand lookups for the default printable ASCII set, but runs to millions for
+/// glyph sets of a few thousand characters.
+fn kerning_pairs(font: &Font, scale: Scale, glyphs: &[char], cell_size: usize) -> HashMap<(char, char), f32> {
+    // Characters the font has no glyph for all draw the missing glyph box, which is never kerned.
+    let glyph_ids: Vec<(char, GlyphId)> = glyphs.iter()
+        .map(|&ch| (ch, font.glyph(ch).id()))
+        .filter(|&(_, id)| id.0 != 0)
+        .collect();
+
+    let mut kerning_pairs = HashMap::new();
+    for &(left, left_id) in glyph_ids.iter() {
+        for &(right, right_id) in glyph_ids.iter() {
+            let amount = font.pair_kerning(scale, left_id, right_id);
+            if amount != 0.0 {
+                kerning_pairs.insert((left, right), amount / (cell_size as f32));
+            }
+        }
+    }
+
+    kerning_pairs
+}
+
+/// Rasterize the glyphs into a square grid of cells. Every glyph shares the same
+/// baseline inside its cell, so none of them need a y-offset.
+fn generate(font: &Font, config: &Config) -> (AtlasImage, FontAtlas) {
+    let cell_size = config.cell_size;
+    let padding = config.padding as f32;
+    let columns = (config.glyphs.len() as f32).sqrt().ceil() as usize;
+    let rows = (config.glyphs.len() + columns - 1) / columns;
+    let mut image = AtlasImage::new(columns * cell_size, rows * cell_size);
+
+    // Scale the font so that a full line from descender to ascender fits inside a cell.
+    let unit_metrics = font.v_metrics(Scale::uniform(1.0));
+    let font_px = (cell_size as f32 - 2.0 * padding) / (unit_metrics.ascent - unit_metrics.descent);
+    let scale = Scale::uniform(font_px);
+    let ascent = font.v_metrics(scale).ascent;
+
+    let mut glyph_coords = HashMap::new();
+    let mut glyph_widths = HashMap::new();
+    let mut glyph_y_offsets = HashMap::new();
+    for (i, &ch) in config.glyphs.iter().enumerate() {
+        let glyph = font.glyph(ch);
+        if glyph.id().0 == 0 && ch != ' ' {
+            eprintln!("WARNING: The font has no glyph for {:?}. Rendering its missing glyph box.", ch);
+        }
+
+        let address = Address { row: i / columns, column: i % columns };
+        let cell_x = address.column * cell_size;
+        let cell_y = address.row * cell_size;
+
+        let scaled = glyph.scaled(scale);
+        let advance = scaled.h_metrics().advance_width;
+        let positioned = scaled.positioned(point(padding, padding + ascent));
+        let mut coverage = vec![0.0; cell_size * cell_size];
+        if let Some(bounding_box) = positioned.pixel_bounding_box() {
+            positioned.draw(|x, y, value| {
+                let x = bounding_box.min.x + x as i32;
+                let y = bounding_box.min.y + y as i32;
+                // Clip anything that overhangs the cell, so it does not bleed into its neighbors.
+                if x >= 0 && y >= 0 && (x as usize) < cell_size && (y as usize) < cell_size {
+                    coverage[(y as usize) * cell_size + (x as usize)] = value;
+                }
+            });
+        }
+
+        let alpha = match config.sdf_spread {
+            Some(spread) => coverage_to_distance_field(cell_size, &coverage, spread),
+            None => coverage,
+        };
+        for y in 0..cell_size {
+            for x in 0..cell_size {
+                image.set_alpha(cell_x + x, cell_y + y, alpha[y * cell_size + x]);
+            }
+        }
+
+        glyph_coords.insert(ch, address);
+        glyph_widths.insert(ch, advance / (cell_size as f32));
+        glyph_y_offsets.insert(ch, 0.0);
+    }
+
+    let kerning_pairs = kerning_pairs(font, scale, &config.glyphs, cell_size);
+
+    let atlas = FontAtlas {
+        glyph_y_offsets: glyph_y_offsets,
+        glyph_widths: glyph_widths,
+        glyph_coords: glyph_coords,
+        rows: rows,
+        columns: columns,
+        glyph_rects: HashMap::new(),
+        kerning_pairs: kerning_pairs,
+        fallback_glyph: config.fallback_glyph,
+        distance_field: config.sdf_spread.map(|spread| {
+            DistanceField { spread: spread / (cell_size as f32) }
+        }),
+        missing_glyphs: Default::default(),
+    };
+
+    (image, atlas)
+}
+
+fn write_png(path: &str, image: &AtlasImage) -> Result<(), String> {
+    let file = File::create(path).map_err(|_| format!("Could not create the image file: {}", path))?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), image.width as u32, image.height as u32);
+    encoder.set_color(png::ColorType::RGBA);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()
+        .map_err(|e| format!("Could not write the image file {}: {}", path, e))?;
+    writer.write_image_data(&image.data)
+        .map_err(|e| format!("Could not write the image file {}: {}", path, e))
+}
+
+fn write_json(path: &str, atlas: &FontAtlas) -> Result<(), String> {
+    let file = File::create(path).map_err(|_| format!("Could not create the atlas file: {}", path))?;
+    serde_json::to_writer_pretty(BufWriter::new(file), atlas)
+        .map_err(|e| format!("Could not write the atlas file {}: {}", path, e))
+}
+
+fn run(config: &Config) -> Result<(), String> {
+    let data = fs::read(&config.font_file)
+        .map_err(|_| format!("Could not read the font file: {}", config.font_file))?;
+    let font = Font::try_from_vec(data)
+        .ok_or_else(|| format!("Could not parse the font file: {}", config.font_file))?;
+
+    let (image, atlas) = generate(&font, config);
+    if let Some(fallback) = config.fallback_glyph {
+        if !atlas.has_glyph(fallback) {
+            return Err(format!("The fallback glyph {:?} is not in the glyph set.", fallback));
+        }
+    }
+    atlas.validate().map_err(|e| format!("{}", e))?;
+
+    let png_file = format!("{}.png", config.output_name);
+    let json_file = format!("{}.json", config.output_name);
+    write_png(&png_file, &image)?;
+    write_json(&json_file, &atlas)?;
+
+    println!(
+        "Wrote a {}x{} atlas of {} glyphs to {} and {}",
+        image.width, image.height, config.glyphs.len(), png_file, json_file
+    );
+
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let config = match parse_args(&args) {
+        Ok(val) => val,
+        Err(e) => {
+            eprintln!("{}", e);
+            eprintln!("{}", USAGE);
+            process::exit(1);
+        }
+    };
+
+    if let Err(e) = run(&config) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+
+    const EPSILON: f64 = 1e-6;
+
+    fn assert_distances_eq(result: &[f64], expected: &[f64]) {
+        assert_eq!(result.len(), expected.len());
+        for (i, (&result, &expected)) in result.iter().zip(expected.iter()).enumerate() {
+            assert!(
+                f64::abs(result - expected) < EPSILON,
+                "at index {}: expected {}, got {}", i, expected, result
+            );
+        }
+    }
+
+    fn transform_1d(f: &[f64]) -> Vec<f64> {
+        let mut d = vec![0.0; f.len()];
+        let mut v = vec![0; f.len()];
+        let mut z = vec![0.0; f.len() + 1];
+        distance_transform_1d(f, &mut d, &mut v, &mut z);
+
+        d
+    }
+
+    /// A square grid of `size` pixels on a side, with the pixels at `features` set.
+    fn bitmap(size: usize, features: &[(usize, usize)]) -> Vec<bool> {
+        let mut bitmap = vec![false; size * size];
+        for &(x, y) in features.iter() {
+            bitmap[y * size + x] = true;
+        }
+
+        bitmap
+    }
+
+    #[test]
+    fn test_distance_transform_1d_gives_squared_distance_to_nearest_feature() {
+        let d = transform_1d(&[FAR, 0.0, FAR, FAR, FAR, 0.0, FAR]);
+
+        assert_distances_eq(&d, &[1.0, 0.0, 1.0, 4.0, 1.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn test_distance_transform_1d_adds_the_sampled_function() {
+        // The lower envelope of the parabolas rooted at each sample, not just the
+        // distance to the nearest zero.
+        let d = transform_1d(&[0.0, FAR, FAR, FAR, 2.0]);
+
+        assert_distances_eq(&d, &[0.0, 1.0, 4.0, 3.0, 2.0]);
+    }
+
+    #[test]
+    fn test_distance_transform_1d_single_sample() {
+        assert_distances_eq(&transform_1d(&[0.0]), &[0.0]);
+    }
+
+    #[test]
+    fn test_distance_transform_gives_euclidean_distance_to_single_feature() {
+        let distances = distance_transform(3, &bitmap(3, &[(1, 1)]));
+        let sqrt_2 = f64::sqrt(2.0);
+
+        assert_distances_eq(&distances, &[
+            sqrt_2, 1.0, sqrt_2,
+            1.0,    0.0, 1.0,
+            sqrt_2, 1.0, sqrt_2,
+        ]);
+    }
+
+    #[test]
+    fn test_distance_transform_picks_nearest_of_several_features() {
+        let distances = distance_transform(4, &bitmap(4, &[(0, 0), (3, 3)]));
+
+        assert_distances_eq(&distances, &[
+            0.0, 1.0,            2.0,            3.0,
+            1.0, f64::sqrt(2.0), f64::sqrt(5.0), 2.0,
+            2.0, f64::sqrt(5.0), f64::sqrt(2.0), 1.0,
+            3.0, 2.0,            1.0,            0.0,
+        ]);
+    }
+
+    #[test]
+    fn test_distance_field_rises_inside_the_glyph_and_falls_outside_it() {
+        // A 3x3 square of full coverage in the middle of a 5x5 cell.
+        let mut coverage = vec![0.0; 25];
+        for y in 1..4 {
+            for x in 1..4 {
+                coverage[y * 5 + x] = 1.0;
+            }
+        }
+        let field = coverage_to_distance_field(5, &coverage, 4.0);
+        let at = |x: usize, y: usize| field[y * 5 + x];
+
+        // Each pixel sits one pixel from the nearest pixel on the other side of the edge,
+        // so the field steps by one eighth of a unit across it.
+        assert!(f32::abs(at(1, 2) - 0.625) < 1e-6);
+        assert!(f32::abs(at(0, 2) - 0.375) < 1e-6);
+        // Further from the edge, the field keeps rising inside the glyph and falling outside it.
+        assert!(f32::abs(at(2, 2) - 0.75) < 1e-6);
+        assert!(f32::abs(at(0, 0) - (0.5 - f32::sqrt(2.0) / 8.0)) < 1e-6);
+    }
+}
@@ -0,0 +1,679 @@
+#![allow(dead_code)]
+//! Import font descriptors in the AngelCode BMFont format as font atlases. BMFont
+//! descriptors come in three flavors, all of which describe the same data: a text
+//! format of `tag key=value` lines, an XML format, and a binary format. The loader
+//! detects the flavor from the first bytes of the file.
+//!
+//! The glyphs in a BMFont atlas are packed tightly into the page image instead of
+//! sitting in grid cells, so the imported atlas locates each glyph by its rectangle.
+//! All metrics are converted to units of the font's line height, so text laid out
+//! with a scale of `n` pixels has lines `n` pixels apart. Only single page fonts are
+//! supported. The page image itself is loaded like any other atlas texture.
+use crate::font_atlas;
+use crate::font_atlas::{FontAtlas, GlyphRect};
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+use log::warn;
+
+
+/// The metrics shared by every glyph in the font, from the `common` block.
+#[derive(Copy, Clone, Debug, Default)]
+struct Common {
+    line_height: f32,
+    scale_w: f32,
+    scale_h: f32,
+    pages: usize,
+}
+
+/// A glyph from the `char` block. Positions and sizes are in pixels of the page image.
+#[derive(Copy, Clone, Debug, Default)]
+struct Char {
+    id: i64,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    x_offset: f32,
+    y_offset: f32,
+    x_advance: f32,
+}
+
+/// A kerning pair from the `kerning` block, in pixels.
+#[derive(Copy, Clone, Debug, Default)]
+struct Kerning {
+    first: i64,
+    second: i64,
+    amount: f32,
+}
+
+/// The contents of a BMFont descriptor that are used to build a font atlas.
+#[derive(Clone, Debug, Default)]
+struct Descriptor {
+    common: Option<Common>,
+    chars: Vec<Char>,
+    kernings: Vec<Kerning>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Error {
+    FileNotFound(String),
+    CouldNotParseFontFile(String, String),
+    CouldNotParseBuffer(String),
+    UnsupportedVersion(u8),
+    MissingCommonBlock,
+    MultiplePages(usize),
+    InvalidAtlas(font_atlas::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &Error::FileNotFound(ref file_name) => {
+                write!(f, "Could not open the BMFont file for reading: {}", file_name)
+            }
+            &Error::CouldNotParseFontFile(ref file_name, ref reason) => {
+                write!(f, "The BMFont file exists, but there was an error in parsing it: {}: {}", file_name, reason)
+            }
+            &Error::CouldNotParseBuffer(ref reason) => {
+                write!(f, "Could not parse the BMFont buffer: {}", reason)
+            }
+            &Error::UnsupportedVersion(version) => {
+                write!(f, "Binary BMFont version {} is not supported. Only version 3 is.", version)
+            }
+            &Error::MissingCommonBlock => {
+                write!(f, "The BMFont descriptor has no common block.")
+            }
+            &Error::MultiplePages(pages) => {
+                write!(f, "The BMFont descriptor has {} pages. Only single page fonts are supported.", pages)
+            }
+            &Error::InvalidAtlas(ref e) => {
+                write!(f, "The BMFont descriptor does not make a valid font atlas: {}", e)
+            }
+        }
+    }
+}
+
+/// Split a line of the text format into its tag and its `key=value` attributes.
+/// Values may be quoted, in which case they can contain spaces.
+fn parse_text_line(line: &str) -> Option<(String, HashMap<String, String>)> {
+    let line = line.trim();
+    let tag_end = line.find(char::is_whitespace).unwrap_or(line.len());
+    let tag = &line[..tag_end];
+    if tag.is_empty() {
+        return None;
+    }
+
+    let mut attributes = HashMap::new();
+    let mut rest = line[tag_end..].trim_start();
+    while let Some(equals) = rest.find('=') {
+        let key = rest[..equals].trim();
+        rest = &rest[equals + 1..];
+        let value = if rest.starts_with('"') {
+            let end = rest[1..].find('"').map_or(rest.len(), |end| end + 1);
+            let value = &rest[1..end];
+            rest = &rest[usize::min(end + 1, rest.len())..];
+            value
+        } else {
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            let value = &rest[..end];
+            rest = &rest[end..];
+            value
+        };
+        attributes.insert(String::from(key), String::from(value));
+        rest = rest.trim_start();
+    }
+
+    Some((String::from(tag), attributes))
+}
+
+/// Split a document in the XML format into its elements and their attributes. The
+/// format only uses elements with quoted attributes, so this does not need a full
+/// XML parser. The XML declaration, comments, and closing tags are skipped.
+fn parse_xml_elements(text: &str) -> Vec<(String, HashMap<String, String>)> {
+    let mut elements = vec![];
+    let mut rest = text;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+        if rest.starts_with("!--") {
+            rest = rest.find("-->").map_or("", |end| &rest[end + 3..]);
+            continue;
+        }
+        let end = match rest.find('>') {
+            Some(end) => end,
+            None => break,
+        };
+        let element = &rest[..end];
+        rest = &rest[end + 1..];
+        if element.starts_with('?') || element.starts_with('/') || element.starts_with('!') {
+            continue;
+        }
+        let element = element.trim_end_matches('/');
+        if let Some((tag, attributes)) = parse_text_line(element) {
+            let attributes = attributes.into_iter()
+                .map(|(key, value)| (key, unescape_xml(&value)))
+                .collect();
+            elements.push((tag, attributes));
+        }
+    }
+
+    elements
+}
+
+fn unescape_xml(value: &str) -> String {
+    value.replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+/// Look up a numeric attribute of a tag. Missing attributes default to zero, as they
+/// do in the tools that write the format.
+fn attribute<T: std::str::FromStr + Default>(
+    tag: &str, attributes: &HashMap<String, String>, key: &str) -> Result<T, Error> {
+
+    match attributes.get(key) {
+        Some(value) => value.parse().map_err(|_e| {
+            Error::CouldNotParseBuffer(format!("The {} attribute {}={:?} is not a number.", tag, key, value))
+        }),
+        None => Ok(T::default()),
+    }
+}
+
+/// Collect the blocks of the text and XML formats into a descriptor.
+fn parse_elements(elements: &[(String, HashMap<String, String>)]) -> Result<Descriptor, Error> {
+    let mut descriptor = Descriptor::default();
+    for (tag, attributes) in elements.iter() {
+        match tag.as_str() {
+            "common" => {
+                descriptor.common = Some(Common {
+                    line_height: attribute(tag, attributes, "lineHeight")?,
+                    scale_w: attribute(tag, attributes, "scaleW")?,
+                    scale_h: attribute(tag, attributes, "scaleH")?,
+                    pages: attribute(tag, attributes, "pages")?,
+                });
+            }
+            "char" => {
+                descriptor.chars.push(Char {
+                    id: attribute(tag, attributes, "id")?,
+                    x: attribute(tag, attributes, "x")?,
+                    y: attribute(tag, attributes, "y")?,
+                    width: attribute(tag, attributes, "width")?,
+                    height: attribute(tag, attributes, "height")?,
+                    x_offset: attribute(tag, attributes, "xoffset")?,
+                    y_offset: attribute(tag, attributes, "yoffset")?,
+                    x_advance: attribute(tag, attributes, "xadvance")?,
+                });
+            }
+            "kerning" => {
+                descriptor.kernings.push(Kerning {
+                    first: attribute(tag, attributes, "first")?,
+                    second: attribute(tag, attributes, "second")?,
+                    amount: attribute(tag, attributes, "amount")?,
+                });
+            }
+            _ => {}
+        }
+    }
+
+    Ok(descriptor)
+}
+
+/// A cursor over the little endian fields of a binary descriptor block.
+struct BinaryBlock<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BinaryBlock<'a> {
+    fn new(data: &'a [u8]) -> BinaryBlock<'a> {
+        BinaryBlock { data: data, position: 0 }
+    }
+
+    fn bytes(&mut self, count: usize) -> Result<&'a [u8], Error> {
+        if self.position + count > self.data.len() {
+            return Err(Error::CouldNotParseBuffer(String::from("A binary block ended early.")));
+        }
+        let bytes = &self.data[self.position..self.position + count];
+        self.position += count;
+
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, Error> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, Error> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn i16(&mut self) -> Result<i16, Error> {
+        let bytes = self.bytes(2)?;
+        Ok(i16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, Error> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+}
+
+/// The sizes in bytes of the records in the binary `char` and `kerning` blocks.
+const BINARY_CHAR_SIZE: usize = 20;
+const BINARY_KERNING_SIZE: usize = 10;
+
+/// Parse the binary format: the bytes `BMF`, a version byte, then a sequence of
+/// blocks, each an id byte and a 32 bit size followed by the block's contents.
+fn parse_binary(data: &[u8]) -> Result<Descriptor, Error> {
+    let mut header = BinaryBlock::new(data);
+    header.bytes(3)?;
+    let version = header.u8()?;
+    if version != 3 {
+        return Err(Error::UnsupportedVersion(version));
+    }
+
+    let mut descriptor = Descriptor::default();
+    while header.position < data.len() {
+        let id = header.u8()?;
+        let size = header.u32()? as usize;
+        let mut block = BinaryBlock::new(header.bytes(size)?);
+        match id {
+            2 => {
+                let line_height = block.u16()?;
+                let _base = block.u16()?;
+                let scale_w = block.u16()?;
+                let scale_h = block.u16()?;
+                let pages = block.u16()?;
+                descriptor.common = Some(Common {
+                    line_height: line_height as f32,
+                    scale_w: scale_w as f32,
+                    scale_h: scale_h as f32,
+                    pages: pages as usize,
+                });
+            }
+            4 => {
+                for _ in 0..(size / BINARY_CHAR_SIZE) {
+                    let id = block.u32()?;
+                    let x = block.u16()?;
+                    let y = block.u16()?;
+                    let width = block.u16()?;
+                    let height = block.u16()?;
+                    let x_offset = block.i16()?;
+                    let y_offset = block.i16()?;
+                    let x_advance = block.i16()?;
+                    let _page = block.u8()?;
+                    let _channel = block.u8()?;
+                    descriptor.chars.push(Char {
+                        id: id as i64,
+                        x: x as f32,
+                        y: y as f32,
+                        width: width as f32,
+                        height: height as f32,
+                        x_offset: x_offset as f32,
+                        y_offset: y_offset as f32,
+                        x_advance: x_advance as f32,
+                    });
+                }
+            }
+            5 => {
+                for _ in 0..(size / BINARY_KERNING_SIZE) {
+                    let first = block.u32()?;
+                    let second = block.u32()?;
+                    let amount = block.i16()?;
+                    descriptor.kernings.push(Kerning {
+                        first: first as i64,
+                        second: second as i64,
+                        amount: amount as f32,
+                    });
+                }
+            }
+            // The info and pages blocks do not affect the atlas.
+            _ => {}
+        }
+    }
+
+    Ok(descriptor)
+}
+
+/// Convert a character id from the descriptor, logging the ids that are not
+/// valid characters. Some tools write the glyph for unknown characters with 
+/// the id -1.
+fn to_char(id: i64) -> Option<char> {
+    let ch = if id >= 0 && id <= (u32::MAX as i64) { std::char::from_u32(id as u32) } else { None };
+    if ch.is_none() {
+        warn!("The BMFont character id {} is not a valid character. Skipping it.", id);
+    }
+
+    ch
+}
+
+fn to_font_atlas(descriptor: &Descriptor) -> Result<FontAtlas, Error> {
+    let common = descriptor.common.ok_or(Error::MissingCommonBlock)?;
+    if common.pages > 1 {
+        return Err(Error::MultiplePages(common.pages));
+    }
+    if common.line_height <= 0.0 || common.scale_w <= 0.0 || common.scale_h <= 0.0 {
+        return Err(Error::CouldNotParseBuffer(String::from("The common block has an empty line height or page.")));
+    }
+
+    let line_height = common.line_height;
+    let mut glyph_y_offsets = HashMap::new();
+    let mut glyph_widths = HashMap::new();
+    let mut glyph_rects = HashMap::new();
+    for glyph in descriptor.chars.iter() {
+        let ch = match to_char(glyph.id) {
+            Some(ch) => ch,
+            None => continue,
+        };
+        let rect = GlyphRect {
+            x: glyph.x / common.scale_w,
+            y: glyph.y / common.scale_h,
+            width: glyph.width / common.scale_w,
+            height: glyph.height / common.scale_h,
+            offset_x: glyph.x_offset / line_height,
+            quad_width: glyph.width / line_height,
+            quad_height: glyph.height / line_height,
+        };
+        glyph_rects.insert(ch, rect);
+        glyph_y_offsets.insert(ch, glyph.y_offset / line_height);
+        glyph_widths.insert(ch, glyph.x_advance / line_height);
+    }
+
+    let mut kerning_pairs = HashMap::new();
+    for kerning in descriptor.kernings.iter() {
+        if let (Some(left), Some(right)) = (to_char(kerning.first), to_char(kerning.second)) {
+            kerning_pairs.insert((left, right), kerning.amount / line_height);
+        }
+    }
+
+    let atlas = FontAtlas {
+        glyph_y_offsets: glyph_y_offsets,
+        glyph_widths: glyph_widths,
+        glyph_coords: HashMap::new(),
+        rows: 0,
+        columns: 0,
+        glyph_rects: glyph_rects,
+        kerning_pairs: kerning_pairs,
+        fallback_glyph: None,
+        distance_field: None,
+        missing_glyphs: Default::default(),
+    };
+    atlas.validate().map_err(Error::InvalidAtlas)?;
+
+    Ok(atlas)
+}
+
+/// Load a BMFont descriptor in any of the text, XML, or binary formats.
+pub fn load_reader<R: io::Read>(mut reader: R) -> Result<FontAtlas, Error> {
+    let mut data = vec![];
+    reader.read_to_end(&mut data).map_err(|e| Error::CouldNotParseBuffer(format!("{}", e)))?;
+
+    let descriptor = if data.starts_with(b"BMF") {
+        parse_binary(&data)?
+    } else {
+        let text = String::from_utf8(data).map_err(|_e| {
+            Error::CouldNotParseBuffer(String::from("The descriptor is not binary, but it is not valid UTF-8 text either."))
+        })?;
+        let text = text.trim_start_matches('\u{feff}').trim_start();
+        if text.starts_with('<') {
+            parse_elements(&parse_xml_elements(text))?
+        } else {
+            let lines: Vec<(String, HashMap<String, String>)> = text.lines()
+                .filter_map(parse_text_line)
+                .collect();
+            parse_elements(&lines)?
+        }
+    };
+
+    to_font_atlas(&descriptor)
+}
+
+pub fn load_file<P: AsRef<Path>>(file: P) -> Result<FontAtlas, Error> {
+    let data = match File::open(file.as_ref()) {
+        Ok(handle) => handle,
+        Err(_) => {
+            return Err(
+                Error::FileNotFound(format!("{}", file.as_ref().display()))
+            );
+        }
+    };
+    let font_atlas = match load_reader(data) {
+        Ok(val) => val,
+        Err(Error::CouldNotParseBuffer(reason)) => {
+            return Err(
+                Error::CouldNotParseFontFile(format!("{}", file.as_ref().display()), reason)
+            );
+        }
+        Err(e) => return Err(e),
+    };
+
+    Ok(font_atlas)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+
+    const TEXT_DESCRIPTOR: &str = "\
+info face=\"Test Font\" size=32 bold=0 italic=0
+common lineHeight=32 base=26 scaleW=256 scaleH=128 pages=1 packed=0
+page id=0 file=\"test_font.png\"
+chars count=2
+char id=65   x=0    y=0    width=16   height=32   xoffset=1    yoffset=2    xadvance=16   page=0  chnl=15
+char id=86   x=16   y=32   width=16   height=24   xoffset=0    yoffset=8    xadvance=15   page=0  chnl=15
+kernings count=1
+kerning first=65  second=86  amount=-2
+";
+
+    const XML_DESCRIPTOR: &str = "\
+<?xml version=\"1.0\"?>
+<font>
+  <info face=\"Test &amp; Font\" size=\"32\" />
+  <common lineHeight=\"32\" base=\"26\" scaleW=\"256\" scaleH=\"128\" pages=\"1\" packed=\"0\" />
+  <pages>
+    <page id=\"0\" file=\"test_font.png\" />
+  </pages>
+  <!-- Two glyphs and one kerning pair. -->
+  <chars count=\"2\">
+    <char id=\"65\" x=\"0\" y=\"0\" width=\"16\" height=\"32\" xoffset=\"1\" yoffset=\"2\" xadvance=\"16\" page=\"0\" chnl=\"15\" />
+    <char id=\"86\" x=\"16\" y=\"32\" width=\"16\" height=\"24\" xoffset=\"0\" yoffset=\"8\" xadvance=\"15\" page=\"0\" chnl=\"15\" />
+  </chars>
+  <kernings count=\"1\">
+    <kerning first=\"65\" second=\"86\" amount=\"-2\" />
+  </kernings>
+</font>
+";
+
+    fn common_block(pages: u16) -> Vec<u8> {
+        let mut block = vec![];
+        for &value in [32_u16, 26, 256, 128, pages].iter() {
+            block.extend_from_slice(&value.to_le_bytes());
+        }
+        // The bit field and the four channel settings.
+        block.extend_from_slice(&[0, 0, 0, 0, 0]);
+
+        block
+    }
+
+    fn char_record(id: u32, x: u16, y: u16, width: u16, height: u16, offsets: [i16; 3]) -> Vec<u8> {
+        let mut record = vec![];
+        record.extend_from_slice(&id.to_le_bytes());
+        for &value in [x, y, width, height].iter() {
+            record.extend_from_slice(&value.to_le_bytes());
+        }
+        for &value in offsets.iter() {
+            record.extend_from_slice(&value.to_le_bytes());
+        }
+        // The page and the channel.
+        record.extend_from_slice(&[0, 15]);
+
+        record
+    }
+
+    fn kerning_record(first: u32, second: u32, amount: i16) -> Vec<u8> {
+        let mut record = vec![];
+        record.extend_from_slice(&first.to_le_bytes());
+        record.extend_from_slice(&second.to_le_bytes());
+        record.extend_from_slice(&amount.to_le_bytes());
+
+        record
+    }
+
+    /// Assemble a version 3 binary descriptor from its blocks.
+    fn binary_descriptor(blocks: &[(u8, Vec<u8>)]) -> Vec<u8> {
+        let mut data = b"BMF\x03".to_vec();
+        for &(id, ref block) in blocks.iter() {
+            data.push(id);
+            data.extend_from_slice(&(block.len() as u32).to_le_bytes());
+            data.extend_from_slice(block);
+        }
+
+        data
+    }
+
+    fn binary_chars() -> Vec<u8> {
+        let mut chars = char_record(65, 0, 0, 16, 32, [1, 2, 16]);
+        chars.extend(char_record(86, 16, 32, 16, 24, [0, 8, 15]));
+
+        chars
+    }
+
+    fn load(data: &[u8]) -> Result<FontAtlas, Error> {
+        load_reader(io::Cursor::new(data.to_vec()))
+    }
+
+    fn assert_close(result: f32, expected: f32) {
+        assert!(f32::abs(result - expected) < 1e-6, "expected {}, got {}", expected, result);
+    }
+
+    /// Check the atlas built from the test font, whichever format it came from.
+    fn assert_test_font(atlas: &FontAtlas) {
+        let a = atlas.glyph_rect('A').unwrap();
+        assert_close(a.x, 0.0);
+        assert_close(a.y, 0.0);
+        assert_close(a.width, 16.0 / 256.0);
+        assert_close(a.height, 32.0 / 128.0);
+        assert_close(a.offset_x, 1.0 / 32.0);
+        assert_close(a.quad_width, 0.5);
+        assert_close(a.quad_height, 1.0);
+        assert_close(atlas.glyph_y_offsets[&'A'], 2.0 / 32.0);
+        assert_close(atlas.glyph_widths[&'A'], 0.5);
+
+        let v = atlas.glyph_rect('V').unwrap();
+        assert_close(v.x, 16.0 / 256.0);
+        assert_close(v.y, 32.0 / 128.0);
+        assert_close(v.quad_height, 24.0 / 32.0);
+        assert_close(atlas.glyph_widths[&'V'], 15.0 / 32.0);
+
+        assert_eq!(atlas.rows, 0);
+        assert_eq!(atlas.columns, 0);
+        assert!(atlas.has_glyph('A') && atlas.has_glyph('V'));
+        assert!(!atlas.has_glyph('B'));
+    }
+
+    #[test]
+    fn test_load_text_descriptor() {
+        let atlas = load(TEXT_DESCRIPTOR.as_bytes()).unwrap();
+
+        assert_test_font(&atlas);
+    }
+
+    #[test]
+    fn test_load_xml_descriptor() {
+        let atlas = load(XML_DESCRIPTOR.as_bytes()).unwrap();
+
+        assert_test_font(&atlas);
+    }
+
+    #[test]
+    fn test_load_binary_descriptor() {
+        let data = binary_descriptor(&[
+            (1, b"\x20\x00info block contents".to_vec()),
+            (2, common_block(1)),
+            (3, b"test_font.png\x00".to_vec()),
+            (4, binary_chars()),
+            (5, kerning_record(65, 86, -2)),
+        ]);
+        let atlas = load(&data).unwrap();
+
+        assert_test_font(&atlas);
+        assert_close(atlas.kerning('A', 'V'), -2.0 / 32.0);
+    }
+
+    #[test]
+    fn test_kerning_pairs_are_in_line_height_units() {
+        let atlas = load(TEXT_DESCRIPTOR.as_bytes()).unwrap();
+
+        assert_close(atlas.kerning('A', 'V'), -2.0 / 32.0);
+        assert_close(atlas.kerning('V', 'A'), 0.0);
+        assert_eq!(atlas.kerning_pairs.len(), 1);
+    }
+
+    #[test]
+    fn test_glyph_outside_page_is_rejected() {
+        let text = TEXT_DESCRIPTOR.replace("char id=65   x=0 ", "char id=65   x=250 ");
+
+        match load(text.as_bytes()) {
+            Err(Error::InvalidAtlas(font_atlas::Error::GlyphRectOutOfBounds('A'))) => {}
+            other => panic!("expected GlyphRectOutOfBounds('A'), got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_multiple_pages_are_rejected() {
+        let text = TEXT_DESCRIPTOR.replace("pages=1", "pages=2");
+        let data = binary_descriptor(&[(2, common_block(3)), (4, binary_chars())]);
+
+        match load(text.as_bytes()) {
+            Err(Error::MultiplePages(2)) => {}
+            other => panic!("expected MultiplePages(2), got {:?}", other),
+        }
+        match load(&data) {
+            Err(Error::MultiplePages(3)) => {}
+            other => panic!("expected MultiplePages(3), got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_missing_common_block_is_rejected() {
+        let text: String = TEXT_DESCRIPTOR.lines()
+            .filter(|line| !line.starts_with("common"))
+            .map(|line| format!("{}\n", line))
+            .collect();
+
+        match load(text.as_bytes()) {
+            Err(Error::MissingCommonBlock) => {}
+            other => panic!("expected MissingCommonBlock, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_truncated_binary_block_is_rejected() {
+        let mut data = binary_descriptor(&[(2, common_block(1)), (4, binary_chars())]);
+        data.truncate(data.len() - 7);
+
+        match load(&data) {
+            Err(Error::CouldNotParseBuffer(_)) => {}
+            other => panic!("expected CouldNotParseBuffer, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_unsupported_binary_version_is_rejected() {
+        let mut data = binary_descriptor(&[(2, common_block(1))]);
+        data[3] = 2;
+
+        match load(&data) {
+            Err(Error::UnsupportedVersion(2)) => {}
+            other => panic!("expected UnsupportedVersion(2), got {:?}", other),
+        }
+    }
+}
@@ -4,14 +4,42 @@ use cglinalg::{
     Vector4, 
     Matrix4, 
     Quaternion, 
+    Unit,
 };
+use crate::frustum::{Aabb, Frustum, Sphere};
+use crate::ray::Ray;
 
 
+/// The ways a camera can project the scene onto the screen.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Projection {
+    /// Distant objects look smaller, using the camera's field of view.
+    Perspective,
+    /// Objects look the same size at any distance. The view is `height` world 
+    /// units tall, and as wide as the aspect ratio of the screen makes it.
+    Orthographic { height: f32 },
+}
+
+/// Build the projection matrix for a camera.
+fn projection_matrix(projection: Projection, fov: f32, aspect: f32, near: f32, far: f32) -> Matrix4<f32> {
+    match projection {
+        Projection::Perspective => {
+            Matrix4::from_perspective_fov(Degrees(fov), aspect, near, far)
+        }
+        Projection::Orthographic { height } => {
+            let top = 0.5 * height;
+            let right = top * aspect;
+            Matrix4::from_orthographic(-right, right, -top, top, near, far)
+        }
+    }
+}
+
 pub struct Camera {
     pub near: f32,
     pub far: f32,
     pub fov: f32,
     pub aspect: f32,
+    pub projection: Projection,
     pub proj_mat: Matrix4<f32>,
 
     pub cam_speed: f32,
@@ -33,7 +61,8 @@ impl Camera {
         cam_speed: f32, cam_yaw_speed: f32, cam_pos: Vector3<f32>,
         fwd: Vector4<f32>, rgt: Vector4<f32>, up: Vector4<f32>, axis: Quaternion<f32>) -> Camera {
 
-        let proj_mat = Matrix4::from_perspective_fov(Degrees(fov), aspect, near, far);
+        let projection = Projection::Perspective;
+        let proj_mat = projection_matrix(projection, fov, aspect, near, far);
         let trans_mat_inv = Matrix4::from_affine_translation(&cam_pos);
         let rot_mat_inv = Matrix4::from(axis);
         let view_mat = rot_mat_inv.inverse().unwrap() * trans_mat_inv.inverse().unwrap();
@@ -43,6 +72,7 @@ impl Camera {
             far: far,
             fov: fov,
             aspect: aspect,
+            projection: projection,
             proj_mat: proj_mat,
 
             cam_speed: cam_speed,
@@ -58,5 +88,1098 @@ impl Camera {
             view_mat: view_mat,
         }
     }
+
+    #[inline]
+    fn update_proj_mat(&mut self) {
+        self.proj_mat = projection_matrix(self.projection, self.fov, self.aspect, self.near, self.far);
+    }
+
+    /// Change the camera's vertical field of view to `fov` degrees and rebuild its
+    /// projection matrix. The field of view only affects perspective projections.
+    pub fn set_fov(&mut self, fov: f32) {
+        self.fov = fov;
+        self.update_proj_mat();
+    }
+
+    /// Change the aspect ratio of the camera's view, when the screen changes size,
+    /// and rebuild its projection matrix.
+    pub fn set_aspect(&mut self, aspect: f32) {
+        self.aspect = aspect;
+        self.update_proj_mat();
+    }
+
+    /// Change the way the camera projects the scene and rebuild its projection matrix.
+    pub fn set_projection(&mut self, projection: Projection) {
+        self.projection = projection;
+        self.update_proj_mat();
+    }
+
+    /// Rotate the camera by `angle` about one of its own axes, given in the camera's
+    /// local frame. Rotating about a local axis is the same as applying the rotation
+    /// before the camera's orientation, so this does not depend on the local axes 
+    /// being up to date.
+    fn rotate_local(&mut self, local_axis: Vector3<f32>, angle: Degrees<f32>) {
+        let q_local = Quaternion::from_axis_angle(&Unit::from_value(local_axis), angle);
+        self.axis = self.axis * &q_local;
+    }
+
+    /// Turn the camera left by `angle` about its up axis. Negative angles turn right.
+    pub fn yaw(&mut self, angle: Degrees<f32>) {
+        self.rotate_local(Vector3::new(0.0, 1.0, 0.0), angle);
+    }
+
+    /// Tilt the camera up by `angle` about its right axis. Negative angles tilt down.
+    pub fn pitch(&mut self, angle: Degrees<f32>) {
+        self.rotate_local(Vector3::new(1.0, 0.0, 0.0), angle);
+    }
+
+    /// Roll the camera clockwise by `angle` about its forward axis, as seen from 
+    /// behind the camera. Negative angles roll counterclockwise.
+    pub fn roll(&mut self, angle: Degrees<f32>) {
+        self.rotate_local(Vector3::new(0.0, 0.0, -1.0), angle);
+    }
+
+    /// Move the camera by `offset`, given in the camera's local frame: positive x
+    /// moves right, positive y moves up, and positive z moves backwards.
+    pub fn translate_local(&mut self, offset: &Vector3<f32>) {
+        let rot_mat = Matrix4::from(self.axis);
+        let world_offset = rot_mat * Vector4::new(offset.x, offset.y, offset.z, 0.0);
+        self.cam_pos += world_offset.contract();
+    }
+
+    /// Recalculate the camera's local axes and its rotation, translation, and view
+    /// matrices from its orientation and position. Call this after moving or turning
+    /// the camera, before reading any of them.
+    pub fn recompute_matrices(&mut self) {
+        self.rot_mat_inv = Matrix4::from(self.axis);
+        self.fwd = self.rot_mat_inv * Vector4::new(0.0, 0.0, -1.0, 0.0);
+        self.rgt = self.rot_mat_inv * Vector4::new(1.0, 0.0,  0.0, 0.0);
+        self.up  = self.rot_mat_inv * Vector4::new(0.0, 1.0,  0.0, 0.0);
+        self.trans_mat_inv = Matrix4::from_affine_translation(&self.cam_pos);
+        self.view_mat = self.rot_mat_inv.inverse().unwrap() * self.trans_mat_inv.inverse().unwrap();
+    }
+
+    /// The region of the world the camera can see, from its current projection
+    /// and view matrices.
+    pub fn frustum(&self) -> Frustum {
+        Frustum::from_matrix(&(self.proj_mat * self.view_mat))
+    }
+
+    /// The ray through the point `(x, y)` on a screen `width` by `height` pixels,
+    /// measured in pixels from the top left corner, like cursor positions. The ray
+    /// starts on the near plane, and works for either projection.
+    pub fn screen_ray(&self, x: f64, y: f64, width: f64, height: f64) -> Ray {
+        let ndc_x = (2.0 * x / width - 1.0) as f32;
+        let ndc_y = (1.0 - 2.0 * y / height) as f32;
+        let proj_view_mat_inv = (self.proj_mat * self.view_mat).inverse().unwrap();
+        let unproject = |ndc_z: f32| {
+            let point = proj_view_mat_inv * Vector4::new(ndc_x, ndc_y, ndc_z, 1.0);
+            point.contract() * (1.0 / point.w)
+        };
+        let near_point = unproject(-1.0);
+        let far_point = unproject(1.0);
+
+        Ray::new(near_point, far_point - near_point)
+    }
+}
+
+/// The direction the world's up axis points. The ground lies in the xy-plane.
+#[inline]
+fn world_up() -> Vector3<f32> {
+    Vector3::new(0.0, 0.0, 1.0)
+}
+
+/// The orientation of a camera with no roll, facing the compass heading `heading`
+/// and tilted up from looking straight down by `tilt`, both in degrees. A heading
+/// of zero faces the positive y-axis, and a tilt of 90 degrees looks at the horizon.
+fn level_orientation(heading: f32, tilt: f32) -> Quaternion<f32> {
+    let q_heading = Quaternion::from_axis_angle(&Unit::from_value(world_up()), Degrees(heading));
+    let q_tilt = Quaternion::from_axis_angle(&Unit::from_value(Vector3::new(1.0, 0.0, 0.0)), Degrees(tilt));
+
+    q_heading * &q_tilt
+}
+
+/// The compass heading in degrees of a camera facing `fwd`, using the same
+/// convention as `level_orientation`.
+fn heading_of(fwd: &Vector4<f32>) -> f32 {
+    f32::atan2(-fwd.x, fwd.y).to_degrees()
+}
+
+/// The angle in degrees that a camera facing `fwd` looks above the horizon.
+fn elevation_of(fwd: &Vector4<f32>) -> f32 {
+    f32::asin(f32::max(-1.0, f32::min(fwd.z, 1.0))).to_degrees()
+}
+
+/// The navigation input for one frame, independent of the device it came from. 
+/// The movement and turning fields are rates between -1 and 1, which the camera
+/// controllers scale by the camera's speeds and the frame time. Movement is in the
+/// camera's local frame: positive x moves right, positive y moves up, and positive
+/// z moves backwards. Positive yaw turns left, positive pitch looks up, and positive
+/// roll rolls right.
+///
+/// The look fields are angles in degrees to turn the camera by this frame, for 
+/// devices such as a mouse that give a distance to turn rather than a rate.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct CameraInput {
+    pub move_x: f32,
+    pub move_y: f32,
+    pub move_z: f32,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub roll: f32,
+    pub look_yaw: f32,
+    pub look_pitch: f32,
+}
+
+impl CameraInput {
+    pub fn new() -> CameraInput {
+        CameraInput {
+            move_x: 0.0, move_y: 0.0, move_z: 0.0,
+            yaw: 0.0, pitch: 0.0, roll: 0.0,
+            look_yaw: 0.0, look_pitch: 0.0,
+        }
+    }
+
+    /// The angles in degrees to yaw and pitch the camera this frame, combining
+    /// the turning rates with the look angles.
+    #[inline]
+    fn turn(&self, camera: &Camera, elapsed_seconds: f32) -> (f32, f32) {
+        let turn = camera.cam_yaw_speed * elapsed_seconds;
+
+        (self.yaw * turn + self.look_yaw, self.pitch * turn + self.look_pitch)
+    }
+
+    /// Determine whether the input asks the camera to do anything.
+    pub fn is_idle(&self) -> bool {
+        *self == CameraInput::new()
+    }
+}
+
+/// The settings for turning the camera with the mouse.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MouseLook {
+    /// The angle in degrees the camera turns per pixel the cursor moves.
+    pub sensitivity: f32,
+    /// Whether moving the mouse forward looks down instead of up.
+    pub invert_y: bool,
+    /// The steepest angle in degrees the mouse can make the camera look up or
+    /// down, or `None` to let it turn all the way over.
+    pub max_pitch: Option<f32>,
+}
+
+impl MouseLook {
+    pub const fn new(sensitivity: f32, invert_y: bool, max_pitch: Option<f32>) -> MouseLook {
+        MouseLook { sensitivity: sensitivity, invert_y: invert_y, max_pitch: max_pitch }
+    }
+
+    /// Turn a cursor movement of `(dx, dy)` pixels into look angles for `camera`,
+    /// adding them to `input`. Screen coordinates grow down, so moving the mouse
+    /// forward looks up unless the y-axis is inverted.
+    pub fn look(&self, camera: &Camera, dx: f64, dy: f64, input: &mut CameraInput) {
+        let dy = if self.invert_y { -dy } else { dy };
+        input.look_yaw -= (dx as f32) * self.sensitivity;
+        let mut look_pitch = -(dy as f32) * self.sensitivity;
+        if let Some(max_pitch) = self.max_pitch {
+            let elevation = elevation_of(&camera.fwd) + input.look_pitch;
+            // Never push a camera that is already past the limit further out, but
+            // let it turn back freely.
+            look_pitch = if look_pitch > 0.0 {
+                f32::min(look_pitch, f32::max(0.0, max_pitch - elevation))
+            } else {
+                f32::max(look_pitch, f32::min(0.0, -max_pitch - elevation))
+            };
+        }
+        input.look_pitch += look_pitch;
+    }
+}
+
+/// Velocities below this are treated as stopped, so a coasting camera comes to rest.
+const MIN_SMOOTHED_SPEED: f32 = 1e-3;
+
+/// Smooths the navigation input over time, so the camera speeds up and slows down
+/// instead of starting and stopping instantly. The camera's velocity is kept as a
+/// multiple of its speed settings, in the same units as the input rates, and the
+/// smoothed input is the velocity for this frame.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct CameraMotion {
+    /// How quickly the velocity changes towards the input, in rates per second.
+    pub acceleration: f32,
+    /// The largest movement rate the camera reaches without sprinting.
+    pub max_speed: f32,
+    /// How quickly the camera coasts to a stop with no movement input. The velocity
+    /// decays by a factor of `e` every `1 / damping` seconds.
+    pub damping: f32,
+    /// The factor sprinting multiplies the movement rate and the top speed by.
+    pub sprint_multiplier: f32,
+    /// The time in seconds the turning rates take to catch up with most of a change
+    /// in the input. Zero turns without smoothing.
+    pub turn_smoothing: f32,
+    velocity: Vector3<f32>,
+    angular_velocity: Vector3<f32>,
+}
+
+impl CameraMotion {
+    pub fn new(acceleration: f32, max_speed: f32, damping: f32, sprint_multiplier: f32, turn_smoothing: f32) -> CameraMotion {
+        CameraMotion {
+            acceleration: acceleration,
+            max_speed: max_speed,
+            damping: damping,
+            sprint_multiplier: sprint_multiplier,
+            turn_smoothing: turn_smoothing,
+            velocity: Vector3::new(0.0, 0.0, 0.0),
+            angular_velocity: Vector3::new(0.0, 0.0, 0.0),
+        }
+    }
+
+    /// Stop the camera dead, for when it jumps to a new place.
+    pub fn reset(&mut self) {
+        self.velocity = Vector3::new(0.0, 0.0, 0.0);
+        self.angular_velocity = Vector3::new(0.0, 0.0, 0.0);
+    }
+
+    /// Advance the camera's velocity by `elapsed_seconds` towards the input, and
+    /// return the smoothed input to hand to a camera controller. The look angles
+    /// pass through unchanged, since the mouse already moves smoothly.
+    pub fn smooth(&mut self, input: &CameraInput, sprinting: bool, elapsed_seconds: f32) -> CameraInput {
+        let boost = if sprinting { self.sprint_multiplier } else { 1.0 };
+        let target = Vector3::new(input.move_x, input.move_y, input.move_z) * boost;
+        if target.magnitude() > 0.0 {
+            let delta = target - self.velocity;
+            let step = self.acceleration * elapsed_seconds;
+            if delta.magnitude() <= step {
+                self.velocity = target;
+            } else {
+                self.velocity += delta.normalize() * step;
+            }
+        } else {
+            self.velocity = self.velocity * f32::exp(-self.damping * elapsed_seconds);
+        }
+        let max_speed = self.max_speed * boost;
+        let speed = self.velocity.magnitude();
+        if speed > max_speed {
+            self.velocity = self.velocity * (max_speed / speed);
+        } else if speed < MIN_SMOOTHED_SPEED {
+            self.velocity = Vector3::new(0.0, 0.0, 0.0);
+        }
+
+        let target_turn = Vector3::new(input.yaw, input.pitch, input.roll);
+        if self.turn_smoothing > 0.0 {
+            let blend = 1.0 - f32::exp(-elapsed_seconds / self.turn_smoothing);
+            self.angular_velocity += (target_turn - self.angular_velocity) * blend;
+        } else {
+            self.angular_velocity = target_turn;
+        }
+        if (self.angular_velocity - target_turn).magnitude() < MIN_SMOOTHED_SPEED {
+            self.angular_velocity = target_turn;
+        }
+
+        CameraInput {
+            move_x: self.velocity.x,
+            move_y: self.velocity.y,
+            move_z: self.velocity.z,
+            yaw: self.angular_velocity.x,
+            pitch: self.angular_velocity.y,
+            roll: self.angular_velocity.z,
+            look_yaw: input.look_yaw,
+            look_pitch: input.look_pitch,
+        }
+    }
+}
+
+/// A volume in the scene the camera cannot pass through.
+#[allow(dead_code)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum BoundingVolume {
+    Box(Aabb),
+    Sphere(Sphere),
+}
+
+/// Limits on where the camera can go. The camera is treated as a ball of `radius`
+/// world units, so it stops short of walls instead of clipping into them. When a 
+/// move runs into a limit, only the part of the move into the limit is taken back,
+/// so the camera slides along walls and the ground instead of stopping dead.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CameraConstraints {
+    pub radius: f32,
+    /// The height of the ground plane.
+    pub ground_height: f32,
+    /// The least height above the ground the camera can go, or `None` to let the
+    /// camera through the ground.
+    pub min_height: Option<f32>,
+    /// The box the camera cannot leave, or `None` to let the camera go anywhere.
+    pub world_bounds: Option<Aabb>,
+    pub obstacles: Vec<BoundingVolume>,
+}
+
+impl CameraConstraints {
+    pub fn new(radius: f32) -> CameraConstraints {
+        CameraConstraints {
+            radius: radius,
+            ground_height: 0.0,
+            min_height: None,
+            world_bounds: None,
+            obstacles: vec![],
+        }
+    }
+
+    /// Push a position moving out of `previous_pos` out of a box grown by the camera's
+    /// radius. The position leaves the box through the face it came in by, so the 
+    /// camera keeps its motion along that face.
+    fn push_out_of_box(&self, aabb: &Aabb, previous_pos: &Vector3<f32>, pos: &mut Vector3<f32>) {
+        let min = [aabb.min.x - self.radius, aabb.min.y - self.radius, aabb.min.z - self.radius];
+        let max = [aabb.max.x + self.radius, aabb.max.y + self.radius, aabb.max.z + self.radius];
+        let previous = [previous_pos.x, previous_pos.y, previous_pos.z];
+        let mut point = [pos.x, pos.y, pos.z];
+        if (0..3).any(|axis| point[axis] <= min[axis] || point[axis] >= max[axis]) {
+            return;
+        }
+
+        // Find the shallowest way out of the box, preferring the faces the camera
+        // was outside of before it moved.
+        let mut best: Option<(bool, f32, usize, f32)> = None;
+        for axis in 0..3 {
+            let faces = [
+                (min[axis], point[axis] - min[axis], previous[axis] <= min[axis]),
+                (max[axis], max[axis] - point[axis], previous[axis] >= max[axis]),
+            ];
+            for &(face, depth, entered) in faces.iter() {
+                let better = match best {
+                    None => true,
+                    Some((best_entered, best_depth, _, _)) => {
+                        (entered && !best_entered) || (entered == best_entered && depth < best_depth)
+                    }
+                };
+                if better {
+                    best = Some((entered, depth, axis, face));
+                }
+            }
+        }
+        if let Some((_, _, axis, face)) = best {
+            point[axis] = face;
+        }
+        *pos = Vector3::new(point[0], point[1], point[2]);
+    }
+
+    /// Push a position out of a sphere grown by the camera's radius, straight out
+    /// from the center, so the camera slides around the sphere.
+    fn push_out_of_sphere(&self, sphere: &Sphere, pos: &mut Vector3<f32>) {
+        let offset = *pos - sphere.center;
+        let distance = offset.magnitude();
+        let min_distance = sphere.radius + self.radius;
+        if distance >= min_distance {
+            return;
+        }
+        let direction = if distance > 0.0 { offset * (1.0 / distance) } else { world_up() };
+        *pos = sphere.center + direction * min_distance;
+    }
+
+    /// Correct the camera's position after it moved from `previous_pos`, so it
+    /// stays within the limits. Returns whether the camera had to be moved.
+    pub fn constrain(&self, camera: &mut Camera, previous_pos: &Vector3<f32>) -> bool {
+        let mut pos = camera.cam_pos;
+        for obstacle in self.obstacles.iter() {
+            match *obstacle {
+                BoundingVolume::Box(ref aabb) => self.push_out_of_box(aabb, previous_pos, &mut pos),
+                BoundingVolume::Sphere(ref sphere) => self.push_out_of_sphere(sphere, &mut pos),
+            }
+        }
+        if let Some(ref bounds) = self.world_bounds {
+            let clamp = |value: f32, min: f32, max: f32| {
+                if min + self.radius > max - self.radius {
+                    0.5 * (min + max)
+                } else {
+                    f32::max(min + self.radius, f32::min(value, max - self.radius))
+                }
+            };
+            pos.x = clamp(pos.x, bounds.min.x, bounds.max.x);
+            pos.y = clamp(pos.y, bounds.min.y, bounds.max.y);
+            pos.z = clamp(pos.z, bounds.min.z, bounds.max.z);
+        }
+        if let Some(min_height) = self.min_height {
+            pos.z = f32::max(pos.z, self.ground_height + min_height);
+        }
+
+        if pos == camera.cam_pos {
+            return false;
+        }
+        camera.cam_pos = pos;
+        camera.recompute_matrices();
+
+        true
+    }
+}
+
+/// A navigation mode for the camera. A controller turns the input for each frame
+/// into camera motion, and keeps the camera's axes and view matrix up to date.
+pub trait CameraController {
+    /// The name of the navigation mode.
+    fn name(&self) -> &'static str;
+
+    /// Take control of the camera from wherever it is, so switching modes does
+    /// not make the camera jump more than the mode requires.
+    fn attach(&mut self, camera: &mut Camera);
+
+    /// Move the camera by one frame of input that lasted `elapsed_seconds`.
+    /// Returns whether the camera moved.
+    fn update(&mut self, camera: &mut Camera, input: &CameraInput, elapsed_seconds: f32) -> bool;
+}
+
+/// Fly the camera freely in any direction, turning about its own axes.
+#[derive(Default)]
+pub struct FreeFlyController {}
+
+impl FreeFlyController {
+    pub fn new() -> FreeFlyController {
+        FreeFlyController {}
+    }
+}
+
+impl CameraController for FreeFlyController {
+    fn name(&self) -> &'static str {
+        "free-fly"
+    }
+
+    fn attach(&mut self, camera: &mut Camera) {
+        camera.recompute_matrices();
+    }
+
+    fn update(&mut self, camera: &mut Camera, input: &CameraInput, elapsed_seconds: f32) -> bool {
+        if input.is_idle() {
+            return false;
+        }
+
+        let (yaw, pitch) = input.turn(camera, elapsed_seconds);
+        camera.yaw(Degrees(yaw));
+        camera.pitch(Degrees(pitch));
+        camera.roll(Degrees(input.roll * camera.cam_yaw_speed * elapsed_seconds));
+
+        let step = camera.cam_speed * elapsed_seconds;
+        camera.translate_local(&Vector3::new(input.move_x * step, input.move_y * step, input.move_z * step));
+        camera.recompute_matrices();
+
+        true
+    }
+}
+
+/// Orbit the camera around a target point, always looking at it. Yawing circles 
+/// around the target, pitching raises and lowers the camera over it, moving 
+/// forwards and backwards zooms in and out, and moving sideways or up and down 
+/// pans the target.
+pub struct OrbitController {
+    pub target: Vector3<f32>,
+    pub distance: f32,
+    pub min_distance: f32,
+    pub max_distance: f32,
+    /// The compass heading of the camera's view of the target, in degrees.
+    pub azimuth: f32,
+    /// The angle in degrees that the camera looks down on the target.
+    pub elevation: f32,
+}
+
+/// The steepest angle in degrees the orbit camera looks down or up at the target.
+/// Looking straight down would leave the heading undefined.
+const MAX_ORBIT_ELEVATION: f32 = 85.0;
+
+impl OrbitController {
+    /// Construct an orbit controller that orbits the point `distance` units in
+    /// front of the camera when it is attached.
+    pub fn new(distance: f32, min_distance: f32, max_distance: f32) -> OrbitController {
+        OrbitController {
+            target: Vector3::new(0.0, 0.0, 0.0),
+            distance: distance,
+            min_distance: min_distance,
+            max_distance: max_distance,
+            azimuth: 0.0,
+            elevation: 0.0,
+        }
+    }
+
+    fn place_camera(&self, camera: &mut Camera) {
+        camera.axis = level_orientation(self.azimuth, 90.0 - self.elevation);
+        camera.cam_pos = self.target;
+        camera.translate_local(&Vector3::new(0.0, 0.0, self.distance));
+        camera.recompute_matrices();
+    }
+}
+
+impl CameraController for OrbitController {
+    fn name(&self) -> &'static str {
+        "orbit"
+    }
+
+    fn attach(&mut self, camera: &mut Camera) {
+        self.distance = f32::max(self.min_distance, f32::min(self.distance, self.max_distance));
+        self.target = camera.cam_pos + camera.fwd.contract() * self.distance;
+        self.azimuth = heading_of(&camera.fwd);
+        let elevation = -elevation_of(&camera.fwd);
+        self.elevation = f32::max(-MAX_ORBIT_ELEVATION, f32::min(elevation, MAX_ORBIT_ELEVATION));
+        self.place_camera(camera);
+    }
+
+    fn update(&mut self, camera: &mut Camera, input: &CameraInput, elapsed_seconds: f32) -> bool {
+        if input.is_idle() {
+            return false;
+        }
+
+        let (yaw, pitch) = input.turn(camera, elapsed_seconds);
+        self.azimuth += yaw;
+        let elevation = self.elevation - pitch;
+        self.elevation = f32::max(-MAX_ORBIT_ELEVATION, f32::min(elevation, MAX_ORBIT_ELEVATION));
+
+        let step = camera.cam_speed * elapsed_seconds;
+        let distance = self.distance + input.move_z * step;
+        self.distance = f32::max(self.min_distance, f32::min(distance, self.max_distance));
+        self.target += camera.rgt.contract() * (input.move_x * step);
+        self.target += world_up() * (input.move_y * step);
+        self.place_camera(camera);
+
+        true
+    }
+}
+
+/// Walk the camera over the ground at a fixed eye height, like a first person
+/// game. The camera turns left and right about the world's up axis, looks up 
+/// and down without rolling, and moves only along the ground.
+pub struct WalkController {
+    pub ground_height: f32,
+    pub eye_height: f32,
+    /// The compass heading of the camera, in degrees.
+    pub heading: f32,
+    /// The angle in degrees that the camera looks above the horizon.
+    pub pitch: f32,
+}
+
+/// The steepest angle in degrees a walking camera can look up or down.
+const MAX_WALK_PITCH: f32 = 85.0;
+
+impl WalkController {
+    pub fn new(ground_height: f32, eye_height: f32) -> WalkController {
+        WalkController {
+            ground_height: ground_height,
+            eye_height: eye_height,
+            heading: 0.0,
+            pitch: 0.0,
+        }
+    }
+
+    fn place_camera(&self, camera: &mut Camera) {
+        camera.axis = level_orientation(self.heading, 90.0 + self.pitch);
+        camera.cam_pos.z = self.ground_height + self.eye_height;
+        camera.recompute_matrices();
+    }
 }
 
+impl CameraController for WalkController {
+    fn name(&self) -> &'static str {
+        "walk"
+    }
+
+    fn attach(&mut self, camera: &mut Camera) {
+        self.heading = heading_of(&camera.fwd);
+        let pitch = elevation_of(&camera.fwd);
+        self.pitch = f32::max(-MAX_WALK_PITCH, f32::min(pitch, MAX_WALK_PITCH));
+        self.place_camera(camera);
+    }
+
+    fn update(&mut self, camera: &mut Camera, input: &CameraInput, elapsed_seconds: f32) -> bool {
+        if input.is_idle() {
+            return false;
+        }
+
+        let (yaw, pitch) = input.turn(camera, elapsed_seconds);
+        self.heading += yaw;
+        let pitch = self.pitch + pitch;
+        self.pitch = f32::max(-MAX_WALK_PITCH, f32::min(pitch, MAX_WALK_PITCH));
+
+        // Walk along the ground in the direction the camera is heading.
+        let heading = self.heading.to_radians();
+        let forward = Vector3::new(-f32::sin(heading), f32::cos(heading), 0.0);
+        let right = Vector3::new(f32::cos(heading), f32::sin(heading), 0.0);
+        let step = camera.cam_speed * elapsed_seconds;
+        camera.cam_pos += forward * (-input.move_z * step);
+        camera.cam_pos += right * (input.move_x * step);
+        self.place_camera(camera);
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frustum::{Aabb, Plane, Sphere};
+
+
+    const EPSILON: f32 = 1e-4;
+
+    fn assert_vector3_eq(result: Vector3<f32>, expected: Vector3<f32>) {
+        assert!(
+            (result - expected).magnitude() < EPSILON,
+            "expected {:?}, got {:?}", expected, result
+        );
+    }
+
+    fn assert_vector4_eq(result: Vector4<f32>, expected: Vector4<f32>) {
+        assert!(
+            (result - expected).magnitude() < EPSILON,
+            "expected {:?}, got {:?}", expected, result
+        );
+    }
+
+    fn assert_matrix_eq(result: &Matrix4<f32>, expected: &Matrix4<f32>) {
+        for column in 0..4 {
+            for row in 0..4 {
+                assert!(
+                    f32::abs(result[column][row] - expected[column][row]) < EPSILON,
+                    "expected {:?}, got {:?}", expected, result
+                );
+            }
+        }
+    }
+
+    /// A camera at `cam_pos` looking down the negative z-axis with the positive
+    /// y-axis up, which is the identity orientation.
+    fn camera_at(cam_pos: Vector3<f32>) -> Camera {
+        let fwd = Vector4::new(0.0, 0.0, -1.0, 0.0);
+        let rgt = Vector4::new(1.0, 0.0, 0.0, 0.0);
+        let up = Vector4::new(0.0, 1.0, 0.0, 0.0);
+        let axis = Quaternion::new(1.0, 0.0, 0.0, 0.0);
+        let mut camera = Camera::new(0.1, 100.0, 67.0, 1.0, 3.0, 50.0, cam_pos, fwd, rgt, up, axis);
+        camera.recompute_matrices();
+
+        camera
+    }
+
+    fn assert_axes_orthonormal(camera: &Camera) {
+        let fwd = camera.fwd.contract();
+        let rgt = camera.rgt.contract();
+        let up = camera.up.contract();
+        assert!(f32::abs(fwd.magnitude() - 1.0) < EPSILON);
+        assert!(f32::abs(rgt.magnitude() - 1.0) < EPSILON);
+        assert!(f32::abs(up.magnitude() - 1.0) < EPSILON);
+        assert!(f32::abs(fwd.dot(&rgt)) < EPSILON);
+        assert!(f32::abs(fwd.dot(&up)) < EPSILON);
+        assert!(f32::abs(rgt.dot(&up)) < EPSILON);
+        // The axes form a right handed frame looking down the negative z-axis.
+        assert_vector3_eq(rgt.cross(&up), -fwd);
+    }
+
+    #[test]
+    fn test_orthographic_projection_maps_view_extents_to_screen_edges() {
+        let mut camera = camera_at(Vector3::new(0.0, 0.0, 0.0));
+        camera.set_aspect(2.0);
+        camera.set_projection(Projection::Orthographic { height: 10.0 });
+        let corner = camera.proj_mat * Vector4::new(10.0, 5.0, -50.0, 1.0);
+        let near_corner = camera.proj_mat * Vector4::new(-10.0, -5.0, -1.0, 1.0);
+
+        assert_vector3_eq(Vector3::new(corner.x, corner.y, corner.w), Vector3::new(1.0, 1.0, 1.0));
+        assert_vector3_eq(Vector3::new(near_corner.x, near_corner.y, near_corner.w), Vector3::new(-1.0, -1.0, 1.0));
+    }
+
+    #[test]
+    fn test_changing_aspect_keeps_projection_mode() {
+        let mut camera = camera_at(Vector3::new(0.0, 0.0, 0.0));
+        camera.set_projection(Projection::Orthographic { height: 10.0 });
+        camera.set_aspect(0.5);
+        let expected = Matrix4::from_orthographic(-2.5, 2.5, -5.0, 5.0, 0.1, 100.0);
+
+        assert_matrix_eq(&camera.proj_mat, &expected);
+    }
+
+    #[test]
+    fn test_screen_ray_through_center_points_forward() {
+        let mut camera = camera_at(Vector3::new(1.0, 2.0, 3.0));
+        camera.pitch(Degrees(-30.0));
+        camera.recompute_matrices();
+        let ray = camera.screen_ray(400.0, 300.0, 800.0, 600.0);
+
+        assert_vector3_eq(ray.direction, camera.fwd.contract());
+        assert!((ray.origin - camera.cam_pos).magnitude() < 0.2);
+    }
+
+    #[test]
+    fn test_screen_ray_hits_what_projects_to_the_cursor() {
+        let mut camera = camera_at(Vector3::new(0.0, -5.0, 4.0));
+        camera.yaw(Degrees(10.0));
+        camera.pitch(Degrees(50.0));
+        camera.recompute_matrices();
+        let point = Vector4::new(1.5, 2.0, 0.0, 1.0);
+        let clip = camera.proj_mat * camera.view_mat * point;
+        let x = 800.0 * (0.5 + 0.5 * clip.x / clip.w) as f64;
+        let y = 600.0 * (0.5 - 0.5 * clip.y / clip.w) as f64;
+        let ray = camera.screen_ray(x, y, 800.0, 600.0);
+        let ground = Plane::new(Vector3::new(0.0, 0.0, 1.0), 0.0);
+        let distance = ray.intersect_plane(&ground).unwrap();
+
+        assert!((ray.at(distance) - point.contract()).magnitude() < 1e-3);
+    }
+
+    #[test]
+    fn test_orthographic_screen_rays_are_parallel() {
+        let mut camera = camera_at(Vector3::new(0.0, 0.0, 0.0));
+        camera.set_projection(Projection::Orthographic { height: 10.0 });
+        let corner = camera.screen_ray(0.0, 0.0, 100.0, 100.0);
+        let aabb = Aabb::new(Vector3::new(-6.0, 4.0, -20.0), Vector3::new(-4.0, 6.0, -10.0));
+
+        assert_vector3_eq(corner.direction, Vector3::new(0.0, 0.0, -1.0));
+        assert_vector3_eq(corner.origin, Vector3::new(-5.0, 5.0, -0.1));
+        assert!(f32::abs(corner.intersect_aabb(&aabb).unwrap() - 9.9) < EPSILON);
+    }
+
+    #[test]
+    fn test_camera_motion_accelerates_to_max_speed() {
+        let mut motion = CameraMotion::new(4.0, 1.0, 5.0, 2.0, 0.0);
+        let mut input = CameraInput::new();
+        input.move_z = -1.0;
+
+        assert!(f32::abs(motion.smooth(&input, false, 0.125).move_z + 0.5) < EPSILON);
+        assert!(f32::abs(motion.smooth(&input, false, 0.5).move_z + 1.0) < EPSILON);
+        assert!(f32::abs(motion.smooth(&input, true, 1.0).move_z + 2.0) < EPSILON);
+    }
+
+    #[test]
+    fn test_camera_motion_coasts_to_a_stop() {
+        let mut motion = CameraMotion::new(100.0, 1.0, 5.0, 2.0, 0.1);
+        let mut input = CameraInput::new();
+        input.move_x = 1.0;
+        input.yaw = 1.0;
+        motion.smooth(&input, false, 1.0);
+        let coasting = motion.smooth(&CameraInput::new(), false, 0.1);
+
+        assert!(coasting.move_x > 0.0 && coasting.move_x < 1.0);
+        assert!(coasting.yaw > 0.0 && coasting.yaw < 1.0);
+        assert!(motion.smooth(&CameraInput::new(), false, 10.0).is_idle());
+    }
+
+    #[test]
+    fn test_constraints_keep_camera_above_ground_and_inside_world() {
+        let mut constraints = CameraConstraints::new(0.5);
+        constraints.min_height = Some(1.0);
+        constraints.world_bounds = Some(Aabb::new(Vector3::new(-10.0, -10.0, -10.0), Vector3::new(10.0, 10.0, 10.0)));
+        let mut camera = camera_at(Vector3::new(12.0, 3.0, -2.0));
+
+        assert!(constraints.constrain(&mut camera, &Vector3::new(9.0, 2.0, 2.0)));
+        assert_vector3_eq(camera.cam_pos, Vector3::new(9.5, 3.0, 1.0));
+        assert!(!constraints.constrain(&mut camera, &Vector3::new(9.0, 2.0, 2.0)));
+    }
+
+    #[test]
+    fn test_constraints_slide_camera_along_obstacles() {
+        let mut constraints = CameraConstraints::new(0.5);
+        let wall = Aabb::new(Vector3::new(2.0, -5.0, 0.0), Vector3::new(3.0, 5.0, 4.0));
+        constraints.obstacles.push(BoundingVolume::Box(wall));
+        constraints.obstacles.push(BoundingVolume::Sphere(Sphere::new(Vector3::new(-5.0, 0.0, 0.0), 1.0)));
+
+        // Moving diagonally into the wall keeps the motion along the wall.
+        let mut camera = camera_at(Vector3::new(1.75, 1.0, 2.0));
+        assert!(constraints.constrain(&mut camera, &Vector3::new(1.0, 0.0, 2.0)));
+        assert_vector3_eq(camera.cam_pos, Vector3::new(1.5, 1.0, 2.0));
+
+        let mut camera = camera_at(Vector3::new(-4.0, 0.0, 0.0));
+        assert!(constraints.constrain(&mut camera, &Vector3::new(-3.0, 0.0, 0.0)));
+        assert_vector3_eq(camera.cam_pos, Vector3::new(-3.5, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_identity_camera_at_origin_has_identity_view_matrix() {
+        let camera = camera_at(Vector3::new(0.0, 0.0, 0.0));
+
+        assert_matrix_eq(&camera.view_mat, &Matrix4::identity());
+    }
+
+    #[test]
+    fn test_view_matrix_undoes_camera_translation() {
+        let camera = camera_at(Vector3::new(1.0, 2.0, 3.0));
+        let expected = Matrix4::from_affine_translation(&Vector3::new(-1.0, -2.0, -3.0));
+
+        assert_matrix_eq(&camera.view_mat, &expected);
+    }
+
+    #[test]
+    fn test_yaw_turns_left() {
+        let mut camera = camera_at(Vector3::new(0.0, 0.0, 0.0));
+        camera.yaw(Degrees(90.0));
+        camera.recompute_matrices();
+
+        assert_vector4_eq(camera.fwd, Vector4::new(-1.0, 0.0, 0.0, 0.0));
+        assert_vector4_eq(camera.rgt, Vector4::new(0.0, 0.0, -1.0, 0.0));
+        assert_vector4_eq(camera.up, Vector4::new(0.0, 1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_pitch_looks_up() {
+        let mut camera = camera_at(Vector3::new(0.0, 0.0, 0.0));
+        camera.pitch(Degrees(90.0));
+        camera.recompute_matrices();
+
+        assert_vector4_eq(camera.fwd, Vector4::new(0.0, 1.0, 0.0, 0.0));
+        assert_vector4_eq(camera.up, Vector4::new(0.0, 0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn test_roll_keeps_forward_axis() {
+        let mut camera = camera_at(Vector3::new(0.0, 0.0, 0.0));
+        camera.roll(Degrees(90.0));
+        camera.recompute_matrices();
+
+        assert_vector4_eq(camera.fwd, Vector4::new(0.0, 0.0, -1.0, 0.0));
+        assert_vector4_eq(camera.up, Vector4::new(1.0, 0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_rotations_are_about_local_axes() {
+        // After turning left, pitching up must still tilt about the camera's own
+        // right axis, not the world's x-axis.
+        let mut camera = camera_at(Vector3::new(0.0, 0.0, 0.0));
+        camera.yaw(Degrees(90.0));
+        camera.pitch(Degrees(90.0));
+        camera.recompute_matrices();
+
+        assert_vector4_eq(camera.fwd, Vector4::new(0.0, 1.0, 0.0, 0.0));
+        assert_vector4_eq(camera.rgt, Vector4::new(0.0, 0.0, -1.0, 0.0));
+    }
+
+    #[test]
+    fn test_axes_stay_orthonormal_after_many_rotations() {
+        let mut camera = camera_at(Vector3::new(0.0, 0.0, 0.0));
+        for i in 0..1000 {
+            camera.yaw(Degrees(1.3));
+            camera.pitch(Degrees(-0.7 + (i % 3) as f32));
+            camera.roll(Degrees(0.9));
+        }
+        camera.recompute_matrices();
+
+        assert_axes_orthonormal(&camera);
+    }
+
+    #[test]
+    fn test_translate_local_moves_along_camera_axes() {
+        let mut camera = camera_at(Vector3::new(1.0, 1.0, 1.0));
+        camera.yaw(Degrees(90.0));
+        camera.translate_local(&Vector3::new(0.0, 0.0, -2.0));
+        camera.translate_local(&Vector3::new(3.0, 0.0, 0.0));
+        camera.recompute_matrices();
+
+        assert_vector3_eq(camera.cam_pos, Vector3::new(-1.0, 1.0, -2.0));
+    }
+
+    #[test]
+    fn test_view_matrix_maps_point_ahead_onto_negative_z_axis() {
+        let mut camera = camera_at(Vector3::new(2.0, 0.0, 0.0));
+        camera.yaw(Degrees(90.0));
+        camera.recompute_matrices();
+        let point = Vector4::new(-3.0, 0.0, 0.0, 1.0);
+
+        assert_vector4_eq(camera.view_mat * point, Vector4::new(0.0, 0.0, -5.0, 1.0));
+    }
+
+    #[test]
+    fn test_view_matrix_inverts_camera_pose() {
+        let mut camera = camera_at(Vector3::new(-4.0, 2.5, 7.0));
+        camera.yaw(Degrees(33.0));
+        camera.pitch(Degrees(-20.0));
+        camera.roll(Degrees(10.0));
+        camera.recompute_matrices();
+        let pose = camera.trans_mat_inv * camera.rot_mat_inv;
+
+        assert_matrix_eq(&(camera.view_mat * pose), &Matrix4::identity());
+    }
+
+    #[test]
+    fn test_level_orientation_faces_heading() {
+        let mut camera = camera_at(Vector3::new(0.0, 0.0, 0.0));
+        camera.axis = level_orientation(90.0, 90.0);
+        camera.recompute_matrices();
+
+        assert_vector4_eq(camera.fwd, Vector4::new(-1.0, 0.0, 0.0, 0.0));
+        assert_vector4_eq(camera.up, Vector4::new(0.0, 0.0, 1.0, 0.0));
+        assert!(f32::abs(heading_of(&camera.fwd) - 90.0) < EPSILON);
+        assert!(f32::abs(elevation_of(&camera.fwd)) < EPSILON);
+    }
+
+    /// A level camera at `cam_pos` facing the compass heading `heading`.
+    fn level_camera_at(cam_pos: Vector3<f32>, heading: f32) -> Camera {
+        let mut camera = camera_at(cam_pos);
+        camera.axis = level_orientation(heading, 90.0);
+        camera.recompute_matrices();
+
+        camera
+    }
+
+    fn turning_input(yaw: f32, pitch: f32) -> CameraInput {
+        let mut input = CameraInput::new();
+        input.yaw = yaw;
+        input.pitch = pitch;
+
+        input
+    }
+
+    #[test]
+    fn test_orbit_attaches_in_front_of_the_camera() {
+        let mut camera = level_camera_at(Vector3::new(1.0, 2.0, 3.0), 0.0);
+        let mut orbit = OrbitController::new(5.0, 1.0, 20.0);
+        orbit.attach(&mut camera);
+
+        assert_vector3_eq(orbit.target, Vector3::new(1.0, 7.0, 3.0));
+        assert_vector3_eq(camera.cam_pos, Vector3::new(1.0, 2.0, 3.0));
+    }
+
+    #[test]
+    fn test_orbit_keeps_its_distance_and_looks_at_the_target() {
+        let mut camera = level_camera_at(Vector3::new(0.0, -10.0, 2.0), 0.0);
+        let mut orbit = OrbitController::new(10.0, 1.0, 20.0);
+        orbit.attach(&mut camera);
+        let target = orbit.target;
+        for &(yaw, pitch) in [(1.0, 0.0), (0.0, -1.0), (-0.5, 0.7), (1.0, 1.0)].iter() {
+            assert!(orbit.update(&mut camera, &turning_input(yaw, pitch), 0.5));
+            let to_target = target - camera.cam_pos;
+
+            assert_vector3_eq(orbit.target, target);
+            assert!(f32::abs(to_target.magnitude() - 10.0) < EPSILON);
+            assert_vector3_eq(to_target.normalize(), camera.fwd.contract());
+            assert!(f32::abs(orbit.elevation) <= MAX_ORBIT_ELEVATION);
+        }
+    }
+
+    #[test]
+    fn test_orbit_zoom_is_clamped() {
+        let mut camera = level_camera_at(Vector3::new(0.0, 0.0, 0.0), 0.0);
+        let mut orbit = OrbitController::new(5.0, 2.0, 8.0);
+        orbit.attach(&mut camera);
+        let mut input = CameraInput::new();
+        input.move_z = -1.0;
+        orbit.update(&mut camera, &input, 10.0);
+
+        assert_eq!(orbit.distance, 2.0);
+        assert!(f32::abs((orbit.target - camera.cam_pos).magnitude() - 2.0) < EPSILON);
+
+        input.move_z = 1.0;
+        orbit.update(&mut camera, &input, 10.0);
+
+        assert_eq!(orbit.distance, 8.0);
+    }
+
+    #[test]
+    fn test_walk_puts_the_camera_at_eye_height() {
+        let mut camera = level_camera_at(Vector3::new(3.0, 4.0, 25.0), 30.0);
+        let mut walk = WalkController::new(1.0, 1.7);
+        walk.attach(&mut camera);
+
+        assert_vector3_eq(camera.cam_pos, Vector3::new(3.0, 4.0, 2.7));
+        assert!(f32::abs(walk.heading - 30.0) < EPSILON);
+    }
+
+    #[test]
+    fn test_walking_stays_on_the_ground() {
+        let mut camera = level_camera_at(Vector3::new(0.0, 0.0, 0.0), 0.0);
+        let mut walk = WalkController::new(0.0, 1.7);
+        walk.attach(&mut camera);
+        let mut input = turning_input(0.0, 1.0);
+        input.move_z = -1.0;
+        input.move_y = 1.0;
+        for _ in 0..10 {
+            walk.update(&mut camera, &input, 0.5);
+
+            assert!(f32::abs(camera.cam_pos.z - 1.7) < EPSILON);
+        }
+        // Looking up does not make the camera walk into the air, or slow it down.
+        assert!(f32::abs(camera.cam_pos.y - 15.0) < EPSILON);
+        assert_eq!(walk.pitch, MAX_WALK_PITCH);
+    }
+
+    #[test]
+    fn test_walking_follows_the_heading() {
+        let mut camera = level_camera_at(Vector3::new(0.0, 0.0, 0.0), 90.0);
+        let mut walk = WalkController::new(0.0, 1.7);
+        walk.attach(&mut camera);
+        let mut input = CameraInput::new();
+        input.move_z = -1.0;
+        input.move_x = 1.0;
+        walk.update(&mut camera, &input, 1.0);
+
+        // Facing the negative x-axis, the camera's right is the positive y-axis.
+        assert_vector3_eq(camera.cam_pos, Vector3::new(-3.0, 3.0, 1.7));
+        assert_vector4_eq(camera.fwd, Vector4::new(-1.0, 0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_controllers_ignore_idle_input() {
+        let mut camera = level_camera_at(Vector3::new(0.0, 0.0, 0.0), 0.0);
+        let mut orbit = OrbitController::new(5.0, 1.0, 20.0);
+        let mut walk = WalkController::new(0.0, 1.7);
+
+        orbit.attach(&mut camera);
+        assert!(!orbit.update(&mut camera, &CameraInput::new(), 1.0));
+        walk.attach(&mut camera);
+        assert!(!walk.update(&mut camera, &CameraInput::new(), 1.0));
+    }
+
+    /// A level camera facing along the positive y-axis, tilted up by `elevation` degrees.
+    fn camera_looking_up(elevation: f32) -> Camera {
+        let mut camera = camera_at(Vector3::new(0.0, 0.0, 0.0));
+        camera.axis = level_orientation(0.0, 90.0 + elevation);
+        camera.recompute_matrices();
+
+        camera
+    }
+
+    fn look(mouse_look: &MouseLook, camera: &Camera, dx: f64, dy: f64) -> CameraInput {
+        let mut input = CameraInput::new();
+        mouse_look.look(camera, dx, dy, &mut input);
+
+        input
+    }
+
+    #[test]
+    fn test_mouse_look_applies_sensitivity() {
+        let camera = camera_looking_up(0.0);
+        let input = look(&MouseLook::new(0.1, false, None), &camera, 20.0, 10.0);
+
+        // Moving the mouse right turns right, and moving it back looks down.
+        assert!(f32::abs(input.look_yaw + 2.0) < EPSILON);
+        assert!(f32::abs(input.look_pitch + 1.0) < EPSILON);
+
+        let doubled = look(&MouseLook::new(0.2, false, None), &camera, 20.0, 10.0);
+        assert!(f32::abs(doubled.look_yaw + 4.0) < EPSILON);
+        assert!(f32::abs(doubled.look_pitch + 2.0) < EPSILON);
+    }
+
+    #[test]
+    fn test_mouse_look_inverts_y() {
+        let camera = camera_looking_up(0.0);
+        let input = look(&MouseLook::new(0.1, true, None), &camera, 0.0, 10.0);
+
+        assert!(f32::abs(input.look_pitch - 1.0) < EPSILON);
+        assert_eq!(input.look_yaw, 0.0);
+    }
+
+    #[test]
+    fn test_mouse_look_clamps_pitch() {
+        let mouse_look = MouseLook::new(0.1, false, Some(30.0));
+        let camera = camera_looking_up(20.0);
+
+        assert!(f32::abs(look(&mouse_look, &camera, 0.0, -500.0).look_pitch - 10.0) < EPSILON);
+        assert!(f32::abs(look(&mouse_look, &camera, 0.0, 1000.0).look_pitch + 50.0) < EPSILON);
+        assert!(f32::abs(look(&mouse_look, &camera, 0.0, -50.0).look_pitch - 5.0) < EPSILON);
+    }
+
+    #[test]
+    fn test_mouse_look_clamps_across_moves_in_one_frame() {
+        let mouse_look = MouseLook::new(0.1, false, Some(30.0));
+        let camera = camera_looking_up(20.0);
+        let mut input = CameraInput::new();
+        mouse_look.look(&camera, 0.0, -80.0, &mut input);
+        mouse_look.look(&camera, 0.0, -80.0, &mut input);
+
+        assert!(f32::abs(input.look_pitch - 10.0) < EPSILON);
+    }
+
+    #[test]
+    fn test_mouse_look_past_the_limit_only_turns_back() {
+        let mouse_look = MouseLook::new(0.1, false, Some(30.0));
+        let camera = camera_looking_up(-60.0);
+
+        assert_eq!(look(&mouse_look, &camera, 0.0, 100.0).look_pitch, 0.0);
+        assert!(f32::abs(look(&mouse_look, &camera, 0.0, -100.0).look_pitch - 10.0) < EPSILON);
+    }
+}
@@ -0,0 +1,189 @@
+#![allow(dead_code)]
+use cglinalg::{
+    Vector3,
+    Quaternion,
+};
+use crate::camera::Camera;
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io;
+use std::fmt;
+use std::path::Path;
+
+
+/// A saved camera view. The orientation is the camera's axis quaternion stored as
+/// `[s, x, y, z]`, and the field of view is in degrees.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CameraBookmark {
+    pub position: [f32; 3],
+    pub axis: [f32; 4],
+    pub fov: f32,
+}
+
+impl CameraBookmark {
+    pub fn from_camera(camera: &Camera) -> CameraBookmark {
+        CameraBookmark {
+            position: [camera.cam_pos.x, camera.cam_pos.y, camera.cam_pos.z],
+            axis: [camera.axis.s, camera.axis.v.x, camera.axis.v.y, camera.axis.v.z],
+            fov: camera.fov,
+        }
+    }
+
+    /// Move the camera to the bookmarked view.
+    pub fn apply(&self, camera: &mut Camera) {
+        camera.cam_pos = Vector3::new(self.position[0], self.position[1], self.position[2]);
+        camera.axis = Quaternion::new(self.axis[0], self.axis[1], self.axis[2], self.axis[3]);
+        camera.set_fov(self.fov);
+        camera.recompute_matrices();
+    }
+}
+
+/// The numbered camera bookmarks.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct CameraBookmarks {
+    pub bookmarks: BTreeMap<usize, CameraBookmark>,
+}
+
+impl CameraBookmarks {
+    pub fn new() -> CameraBookmarks {
+        CameraBookmarks {
+            bookmarks: BTreeMap::new(),
+        }
+    }
+
+    /// Save the camera's current view in bookmark `slot`, replacing any view
+    /// already saved there.
+    pub fn store(&mut self, slot: usize, camera: &Camera) {
+        self.bookmarks.insert(slot, CameraBookmark::from_camera(camera));
+    }
+
+    /// Move the camera to the view saved in bookmark `slot`. Returns whether
+    /// there was a view saved there.
+    pub fn recall(&self, slot: usize, camera: &mut Camera) -> bool {
+        match self.bookmarks.get(&slot) {
+            Some(bookmark) => {
+                bookmark.apply(camera);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Error {
+    FileNotFound(String),
+    CouldNotParseBookmarksFile(String),
+    CouldNotParseBuffer,
+    CouldNotWriteBookmarksFile(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &Error::FileNotFound(ref file_name) => {
+                write!(f, "Could not open the camera bookmarks file for reading: {}", file_name)
+            }
+            &Error::CouldNotParseBookmarksFile(ref file_name) => {
+                write!(f, "The camera bookmarks file exists, but there was an error in parsing it: {}", file_name)
+            }
+            &Error::CouldNotParseBuffer => {
+                write!(f, "Could not parse the camera bookmarks buffer.")
+            }
+            &Error::CouldNotWriteBookmarksFile(ref file_name) => {
+                write!(f, "Could not write the camera bookmarks file: {}", file_name)
+            }
+        }
+    }
+}
+
+pub fn load_reader<R: io::Read>(reader: R) -> Result<CameraBookmarks, Error> {
+    serde_json::from_reader(reader).map_err(|_e| {
+        Error::CouldNotParseBuffer
+    })
+}
+
+pub fn load_file<P: AsRef<Path>>(file: P) -> Result<CameraBookmarks, Error> {
+    let data = match File::open(file.as_ref()) {
+        Ok(handle) => handle,
+        Err(_) => {
+            return Err(
+                Error::FileNotFound(format!("{}", file.as_ref().display()))
+            );
+        }
+    };
+    let bookmarks = match load_reader(data) {
+        Ok(val) => val,
+        Err(Error::CouldNotParseBuffer) => {
+            return Err(
+                Error::CouldNotParseBookmarksFile(format!("{}", file.as_ref().display()))
+            );
+        }
+        Err(e) => return Err(e),
+    };
+
+    Ok(bookmarks)
+}
+
+pub fn write_file<P: AsRef<Path>>(bookmarks: &CameraBookmarks, file: P) -> Result<(), Error> {
+    let error = || Error::CouldNotWriteBookmarksFile(format!("{}", file.as_ref().display()));
+    let handle = File::create(file.as_ref()).map_err(|_e| error())?;
+    serde_json::to_writer_pretty(handle, bookmarks).map_err(|_e| error())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::FRAC_1_SQRT_2;
+
+
+    fn test_bookmarks() -> CameraBookmarks {
+        let mut bookmarks = CameraBookmarks::new();
+        bookmarks.bookmarks.insert(1, CameraBookmark {
+            position: [0.0, -4.5, 2.0],
+            axis: [1.0, 0.0, 0.0, 0.0],
+            fov: 67.0,
+        });
+        bookmarks.bookmarks.insert(7, CameraBookmark {
+            position: [12.25, 3.0, -1.5],
+            axis: [FRAC_1_SQRT_2, 0.0, 0.0, FRAC_1_SQRT_2],
+            fov: 35.5,
+        });
+
+        bookmarks
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let bookmarks = test_bookmarks();
+        let json = serde_json::to_string_pretty(&bookmarks).unwrap();
+        let result = load_reader(json.as_bytes()).unwrap();
+
+        assert_eq!(result.bookmarks, bookmarks.bookmarks);
+    }
+
+    #[test]
+    fn test_round_trip_empty() {
+        let json = serde_json::to_string(&CameraBookmarks::new()).unwrap();
+        let result = load_reader(json.as_bytes()).unwrap();
+
+        assert!(result.bookmarks.is_empty());
+    }
+
+    #[test]
+    fn test_malformed_json() {
+        let inputs = [
+            "",
+            "{\"bookmarks\": {\"1\": {\"position\": [0.0, 1.0, 2.0]",
+            "{\"bookmarks\": {\"1\": {\"position\": [0.0, 1.0], \"axis\": [1.0, 0.0, 0.0, 0.0], \"fov\": 67.0}}}",
+            "{\"bookmarks\": [1, 2, 3]}",
+        ];
+        for input in inputs.iter() {
+            match load_reader(input.as_bytes()) {
+                Err(Error::CouldNotParseBuffer) => {}
+                other => panic!("expected CouldNotParseBuffer for {:?}, got {:?}", input, other),
+            }
+        }
+    }
+}
@@ -0,0 +1,460 @@
+#![allow(dead_code)]
+use cglinalg::{
+    Vector3,
+    Quaternion,
+};
+use crate::camera::Camera;
+
+use std::fs::File;
+use std::io;
+use std::fmt;
+use std::path::Path;
+
+
+/// How the camera speeds up and slows down between two keyframes.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+}
+
+impl Default for Easing {
+    fn default() -> Easing {
+        Easing::Linear
+    }
+}
+
+impl Easing {
+    /// Map the fraction `t` of the time between two keyframes to the fraction of
+    /// the way along the path between them.
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = f32::max(0.0, f32::min(t, 1.0));
+        match *self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => t * (2.0 - t),
+            Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+/// A camera pose on a path. The position is in world space, and the orientation
+/// is stored as `[s, x, y, z]`, in the same form as the camera's axis quaternion.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+pub struct Keyframe {
+    /// The time in seconds from the start of the path that the camera reaches
+    /// the keyframe.
+    pub time: f32,
+    pub position: [f32; 3],
+    pub orientation: [f32; 4],
+    /// The easing of the camera on its way from this keyframe to the next one.
+    #[serde(default)]
+    pub easing: Easing,
+}
+
+impl Keyframe {
+    pub fn new(time: f32, position: Vector3<f32>, orientation: Quaternion<f32>, easing: Easing) -> Keyframe {
+        Keyframe {
+            time: time,
+            position: [position.x, position.y, position.z],
+            orientation: [orientation.s, orientation.v.x, orientation.v.y, orientation.v.z],
+            easing: easing,
+        }
+    }
+
+    #[inline]
+    pub fn position(&self) -> Vector3<f32> {
+        Vector3::new(self.position[0], self.position[1], self.position[2])
+    }
+
+    #[inline]
+    pub fn orientation(&self) -> Quaternion<f32> {
+        Quaternion::new(self.orientation[0], self.orientation[1], self.orientation[2], self.orientation[3])
+    }
+}
+
+/// A scripted route for the camera to fly. The camera passes through every keyframe
+/// on a smooth Catmull-Rom curve, and turns between keyframe orientations along
+/// the shortest arc. A looping path should end on the same pose it starts on, so
+/// the camera flies through the seam without a jolt.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CameraPath {
+    keyframes: Vec<Keyframe>,
+    #[serde(default)]
+    pub looping: bool,
+}
+
+impl CameraPath {
+    /// Construct a path, checking that it has keyframes in order of time.
+    pub fn new(keyframes: Vec<Keyframe>, looping: bool) -> Result<CameraPath, Error> {
+        let path = CameraPath {
+            keyframes: keyframes,
+            looping: looping,
+        };
+        path.validate()?;
+
+        Ok(path)
+    }
+
+    #[inline]
+    pub fn keyframes(&self) -> &[Keyframe] {
+        &self.keyframes
+    }
+
+    /// The time in seconds it takes to fly the path.
+    pub fn duration(&self) -> f32 {
+        match (self.keyframes.first(), self.keyframes.last()) {
+            (Some(first), Some(last)) => last.time - first.time,
+            _ => 0.0,
+        }
+    }
+
+    /// Check that the path has keyframes, and that their times are in order.
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.keyframes.is_empty() {
+            return Err(Error::NoKeyframes);
+        }
+        for i in 1..self.keyframes.len() {
+            if self.keyframes[i].time <= self.keyframes[i - 1].time {
+                return Err(Error::KeyframesOutOfOrder(i));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The index of the keyframe before `i` used to shape the curve. On a looping
+    /// path the first and last keyframes are the same pose, so the neighbor of
+    /// the first keyframe is the one before the last.
+    #[inline]
+    fn previous(&self, i: usize) -> usize {
+        let last = self.keyframes.len() - 1;
+        if i > 0 {
+            i - 1
+        } else if self.looping && last > 0 {
+            last - 1
+        } else {
+            0
+        }
+    }
+
+    #[inline]
+    fn next(&self, i: usize) -> usize {
+        let last = self.keyframes.len() - 1;
+        if i < last {
+            i + 1
+        } else if self.looping && last > 0 {
+            1
+        } else {
+            last
+        }
+    }
+
+    /// The velocity of the camera as it passes keyframe `i`, in world units per second.
+    fn tangent(&self, i: usize) -> Vector3<f32> {
+        let prev = self.previous(i);
+        let next = self.next(i);
+        let prev_time = if prev <= i { self.keyframes[prev].time } else { self.keyframes[prev].time - self.duration() };
+        let next_time = if next >= i { self.keyframes[next].time } else { self.keyframes[next].time + self.duration() };
+        let dt = next_time - prev_time;
+        if dt <= 0.0 {
+            return Vector3::new(0.0, 0.0, 0.0);
+        }
+
+        (self.keyframes[next].position() - self.keyframes[prev].position()) * (1.0 / dt)
+    }
+
+    /// Compute the position and orientation of the camera `seconds` seconds after
+    /// it started flying the path. Before the start and after the end of a path that
+    /// does not loop, the camera holds at the first or the last keyframe.
+    pub fn sample(&self, seconds: f32) -> (Vector3<f32>, Quaternion<f32>) {
+        let first = &self.keyframes[0];
+        let last = &self.keyframes[self.keyframes.len() - 1];
+        let duration = self.duration();
+        let time = if self.looping && duration > 0.0 {
+            first.time + seconds.rem_euclid(duration)
+        } else {
+            first.time + seconds
+        };
+        if time <= first.time {
+            return (first.position(), first.orientation());
+        }
+        if time >= last.time {
+            return (last.position(), last.orientation());
+        }
+
+        let i = self.keyframes.iter().rposition(|keyframe| keyframe.time <= time).unwrap();
+        let start = &self.keyframes[i];
+        let end = &self.keyframes[i + 1];
+        let dt = end.time - start.time;
+        let t = start.easing.apply((time - start.time) / dt);
+
+        // Cubic Hermite interpolation with Catmull-Rom tangents.
+        let t2 = t * t;
+        let t3 = t2 * t;
+        let h00 = 2.0 * t3 - 3.0 * t2 + 1.0;
+        let h10 = t3 - 2.0 * t2 + t;
+        let h01 = -2.0 * t3 + 3.0 * t2;
+        let h11 = t3 - t2;
+        let position = start.position() * h00
+            + self.tangent(i) * (h10 * dt)
+            + end.position() * h01
+            + self.tangent(i + 1) * (h11 * dt);
+
+        // Turn along the shorter of the two arcs between the orientations.
+        let q_start = start.orientation();
+        let mut q_end = end.orientation();
+        if q_start.dot(&q_end) < 0.0 {
+            q_end = -q_end;
+        }
+        let orientation = q_start.slerp(&q_end, t);
+
+        (position, orientation)
+    }
+}
+
+/// Plays a camera path back in real time.
+pub struct CameraPathPlayer {
+    pub path: CameraPath,
+    pub seconds: f32,
+    pub playing: bool,
+}
+
+impl CameraPathPlayer {
+    pub fn new(path: CameraPath) -> CameraPathPlayer {
+        CameraPathPlayer {
+            path: path,
+            seconds: 0.0,
+            playing: false,
+        }
+    }
+
+    /// Determine whether a path that does not loop has played to its end.
+    pub fn is_finished(&self) -> bool {
+        !self.path.looping && self.seconds >= self.path.duration()
+    }
+
+    /// Start or resume playing the path. A finished path starts over.
+    pub fn play(&mut self) {
+        if self.is_finished() {
+            self.seconds = 0.0;
+        }
+        self.playing = true;
+    }
+
+    pub fn pause(&mut self) {
+        self.playing = false;
+    }
+
+    pub fn toggle(&mut self) {
+        if self.playing {
+            self.pause();
+        } else {
+            self.play();
+        }
+    }
+
+    /// Go back to the start of the path.
+    pub fn rewind(&mut self) {
+        self.seconds = 0.0;
+    }
+
+    /// Advance the path by `elapsed_seconds` and fly the camera along it. Returns
+    /// whether the camera moved. The player stops by itself at the end of a path
+    /// that does not loop.
+    pub fn update(&mut self, camera: &mut Camera, elapsed_seconds: f32) -> bool {
+        if !self.playing {
+            return false;
+        }
+
+        self.seconds += elapsed_seconds;
+        if self.is_finished() {
+            self.seconds = self.path.duration();
+            self.playing = false;
+        }
+        let (position, orientation) = self.path.sample(self.seconds);
+        camera.cam_pos = position;
+        camera.axis = orientation;
+        camera.recompute_matrices();
+
+        true
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Error {
+    FileNotFound(String),
+    CouldNotParsePathFile(String),
+    CouldNotParseBuffer,
+    NoKeyframes,
+    KeyframesOutOfOrder(usize),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &Error::FileNotFound(ref file_name) => {
+                write!(f, "Could not open the camera path file for reading: {}", file_name)
+            }
+            &Error::CouldNotParsePathFile(ref file_name) => {
+                write!(f, "The camera path file exists, but there was an error in parsing it: {}", file_name)
+            }
+            &Error::CouldNotParseBuffer => {
+                write!(f, "Could not parse the camera path buffer.")
+            }
+            &Error::NoKeyframes => {
+                write!(f, "The camera path has no keyframes.")
+            }
+            &Error::KeyframesOutOfOrder(index) => {
+                write!(f, "Keyframe {} of the camera path does not come after the keyframe before it.", index)
+            }
+        }
+    }
+}
+
+pub fn load_reader<R: io::Read>(reader: R) -> Result<CameraPath, Error> {
+    let path: CameraPath = serde_json::from_reader(reader).map_err(|_e| {
+        Error::CouldNotParseBuffer
+    })?;
+    path.validate()?;
+
+    Ok(path)
+}
+
+pub fn load_file<P: AsRef<Path>>(file: P) -> Result<CameraPath, Error> {
+    let data = match File::open(file.as_ref()) {
+        Ok(handle) => handle,
+        Err(_) => {
+            return Err(
+                Error::FileNotFound(format!("{}", file.as_ref().display()))
+            );
+        }
+    };
+    let path = match load_reader(data) {
+        Ok(val) => val,
+        Err(Error::CouldNotParseBuffer) => {
+            return Err(
+                Error::CouldNotParsePathFile(format!("{}", file.as_ref().display()))
+            );
+        }
+        Err(e) => return Err(e),
+    };
+
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cglinalg::{Degrees, Unit};
+
+
+    const EPSILON: f32 = 1e-4;
+
+    fn assert_vector3_eq(result: Vector3<f32>, expected: Vector3<f32>) {
+        assert!(
+            (result - expected).magnitude() < EPSILON,
+            "expected {:?}, got {:?}", expected, result
+        );
+    }
+
+    /// Quaternions `q` and `-q` are the same rotation.
+    fn assert_rotation_eq(result: Quaternion<f32>, expected: Quaternion<f32>) {
+        assert!(
+            f32::abs(f32::abs(result.dot(&expected)) - 1.0) < EPSILON,
+            "expected {:?}, got {:?}", expected, result
+        );
+    }
+
+    fn heading(angle: f32) -> Quaternion<f32> {
+        Quaternion::from_axis_angle(&Unit::from_value(Vector3::new(0.0, 0.0, 1.0)), Degrees(angle))
+    }
+
+    fn keyframe(time: f32, position: Vector3<f32>, angle: f32, easing: Easing) -> Keyframe {
+        Keyframe::new(time, position, heading(angle), easing)
+    }
+
+    fn curved_path(looping: bool) -> CameraPath {
+        CameraPath::new(vec![
+            keyframe(0.0, Vector3::new(0.0, 0.0, 2.0), 0.0, Easing::Linear),
+            keyframe(2.0, Vector3::new(4.0, 1.0, 3.0), 90.0, Easing::EaseInOut),
+            keyframe(3.0, Vector3::new(5.0, 6.0, 2.0), 180.0, Easing::Linear),
+            keyframe(5.0, Vector3::new(0.0, 0.0, 2.0), 360.0, Easing::Linear),
+        ], looping).unwrap()
+    }
+
+    #[test]
+    fn test_new_rejects_empty_and_unordered_paths() {
+        let unordered = vec![
+            keyframe(1.0, Vector3::new(0.0, 0.0, 0.0), 0.0, Easing::Linear),
+            keyframe(1.0, Vector3::new(1.0, 0.0, 0.0), 0.0, Easing::Linear),
+        ];
+
+        assert!(match CameraPath::new(vec![], false) { Err(Error::NoKeyframes) => true, _ => false });
+        assert!(match CameraPath::new(unordered, false) { Err(Error::KeyframesOutOfOrder(1)) => true, _ => false });
+    }
+
+    #[test]
+    fn test_path_passes_through_every_keyframe() {
+        let path = curved_path(false);
+        for keyframe in path.keyframes().iter() {
+            let (position, orientation) = path.sample(keyframe.time);
+
+            assert_vector3_eq(position, keyframe.position());
+            assert_rotation_eq(orientation, keyframe.orientation());
+        }
+    }
+
+    #[test]
+    fn test_rotation_reaches_the_next_keyframe_at_the_end_of_a_segment() {
+        let path = curved_path(false);
+        let (_, start) = path.sample(0.0);
+        let (_, end) = path.sample(2.0 - 1e-5);
+        let (_, halfway) = path.sample(1.0);
+
+        assert_rotation_eq(start, heading(0.0));
+        assert_rotation_eq(end, heading(90.0));
+        assert_rotation_eq(halfway, heading(45.0));
+    }
+
+    #[test]
+    fn test_easing_changes_progress_along_a_segment() {
+        let start = Vector3::new(0.0, 0.0, 0.0);
+        let end = Vector3::new(8.0, 0.0, 0.0);
+        let path = |easing| {
+            CameraPath::new(vec![keyframe(0.0, start, 0.0, easing), keyframe(2.0, end, 0.0, easing)], false).unwrap()
+        };
+
+        // The path is a straight line, so the position is proportional to the progress.
+        assert_vector3_eq(path(Easing::Linear).sample(1.0).0, Vector3::new(4.0, 0.0, 0.0));
+        assert_vector3_eq(path(Easing::EaseIn).sample(1.0).0, Vector3::new(2.0, 0.0, 0.0));
+        assert_vector3_eq(path(Easing::EaseOut).sample(1.0).0, Vector3::new(6.0, 0.0, 0.0));
+        assert_vector3_eq(path(Easing::EaseInOut).sample(0.5).0, Vector3::new(1.25, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_looping_path_wraps_around() {
+        let path = curved_path(true);
+        for &seconds in [0.5, 2.5, 4.0].iter() {
+            let (position, orientation) = path.sample(seconds);
+            let (later_position, later_orientation) = path.sample(seconds + path.duration());
+            let (earlier_position, earlier_orientation) = path.sample(seconds - path.duration());
+
+            assert_vector3_eq(later_position, position);
+            assert_vector3_eq(earlier_position, position);
+            assert_rotation_eq(later_orientation, orientation);
+            assert_rotation_eq(earlier_orientation, orientation);
+        }
+    }
+
+    #[test]
+    fn test_path_that_does_not_loop_holds_at_its_ends() {
+        let path = curved_path(false);
+
+        assert_vector3_eq(path.sample(-1.0).0, Vector3::new(0.0, 0.0, 2.0));
+        assert_vector3_eq(path.sample(7.0).0, Vector3::new(0.0, 0.0, 2.0));
+        assert_rotation_eq(path.sample(7.0).1, heading(360.0));
+    }
+}
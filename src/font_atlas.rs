@@ -1,7 +1,11 @@
 use std::fs::File;
 use std::io;
+use std::fmt;
 use std::path::Path;
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+
+use log::warn;
 
 
 #[derive(Copy, Clone, Debug, Serialize, Deserialize)]
@@ -10,6 +14,44 @@ pub struct Address {
     pub column: usize,
 }
 
+/// The horizontal adjustment applied between two adjacent glyphs, in units
+/// of the glyph cell size. A negative amount pulls the glyphs closer together.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+pub struct KerningPair {
+    pub left: char,
+    pub right: char,
+    pub amount: f32,
+}
+
+/// The location of a glyph packed anywhere in the atlas image, for atlases whose
+/// glyphs are not laid out on a grid. The texture rectangle is measured in fractions 
+/// of the atlas image from its top left corner. The offset and the size of the quad 
+/// drawn for the glyph are in units of the glyph cell size, like the glyph's width
+/// and y-offset.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+pub struct GlyphRect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    pub offset_x: f32,
+    pub quad_width: f32,
+    pub quad_height: f32,
+}
+
+/// The parameters of an atlas whose image stores a signed distance field in its 
+/// alpha channel instead of glyph coverage. An alpha value of 0.5 lies on the edge 
+/// of a glyph.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+pub struct DistanceField {
+    /// The distance from the edge of a glyph at which the field saturates, in units
+    /// of the glyph cell size.
+    pub spread: f32,
+}
+
+/// A font atlas. Glyphs are either stored in the cells of a grid of `rows` by 
+/// `columns` cells, each addressed by `glyph_coords`, or packed anywhere in the image
+/// and located by `glyph_rects`. An atlas without a grid has zero rows and columns.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct FontAtlas {
     pub glyph_y_offsets: HashMap<char, f32>,
@@ -17,6 +59,163 @@ pub struct FontAtlas {
     pub glyph_coords: HashMap<char, Address>,
     pub rows: usize,
     pub columns: usize,
+    #[serde(default)]
+    pub glyph_rects: HashMap<char, GlyphRect>,
+    #[serde(default, with = "kerning_pairs")]
+    pub kerning_pairs: HashMap<(char, char), f32>,
+    #[serde(default)]
+    pub fallback_glyph: Option<char>,
+    #[serde(default)]
+    pub distance_field: Option<DistanceField>,
+    /// The characters already reported missing, so each one is only logged once.
+    #[serde(skip)]
+    pub missing_glyphs: RefCell<HashSet<char>>,
+}
+
+impl FontAtlas {
+    /// Look up the kerning adjustment between the glyphs `left` and `right`. 
+    /// Pairs without an entry in the atlas are not adjusted.
+    #[inline]
+    pub fn kerning(&self, left: char, right: char) -> f32 {
+        match self.kerning_pairs.get(&(left, right)) {
+            Some(&amount) => amount,
+            None => 0.0,
+        }
+    }
+
+    /// Find where the glyph `ch` is in the atlas image. A glyph in a grid cell
+    /// covers the whole cell and is drawn as a square quad one cell wide.
+    pub fn glyph_rect(&self, ch: char) -> Option<GlyphRect> {
+        if let Some(&rect) = self.glyph_rects.get(&ch) {
+            return Some(rect);
+        }
+
+        self.glyph_coords.get(&ch).map(|address| {
+            let cell_width = 1.0 / (self.columns as f32);
+            let cell_height = 1.0 / (self.rows as f32);
+            GlyphRect {
+                x: (address.column as f32) * cell_width,
+                y: (address.row as f32) * cell_height,
+                width: cell_width,
+                height: cell_height,
+                offset_x: 0.0,
+                quad_width: 1.0,
+                quad_height: 1.0,
+            }
+        })
+    }
+
+    /// Determine whether the atlas has all the data needed to render the glyph `ch`.
+    #[inline]
+    pub fn has_glyph(&self, ch: char) -> bool {
+        (self.glyph_coords.contains_key(&ch) || self.glyph_rects.contains_key(&ch))
+            && self.glyph_widths.contains_key(&ch) 
+            && self.glyph_y_offsets.contains_key(&ch)
+    }
+
+    /// Find the glyph to render for the character `ch`. When the atlas has no glyph 
+    /// for `ch`, this substitutes the fallback glyph, logging a warning the first
+    /// time. If there is no usable fallback glyph either, the character is skipped.
+    pub fn resolve_glyph(&self, ch: char) -> Option<char> {
+        if self.has_glyph(ch) {
+            return Some(ch);
+        }
+
+        let first_miss = self.missing_glyphs.borrow_mut().insert(ch);
+        match self.fallback_glyph {
+            Some(fallback) if self.has_glyph(fallback) => {
+                if first_miss {
+                    warn!("Font atlas has no glyph for {:?}. Substituting fallback glyph {:?}.", ch, fallback);
+                }
+                Some(fallback)
+            }
+            _ => {
+                if first_miss {
+                    warn!("Font atlas has no glyph for {:?}. Skipping it.", ch);
+                }
+                None
+            }
+        }
+    }
+
+    /// Check that the atlas is internally consistent: every glyph must lie inside
+    /// the atlas grid or the atlas image and have both a width and a y-offset, and 
+    /// the fallback glyph, if there is one, must be in the atlas.
+    pub fn validate(&self) -> Result<(), Error> {
+        let mut glyphs: Vec<char> = self.glyph_coords.keys()
+            .chain(self.glyph_rects.keys())
+            .cloned()
+            .collect();
+        glyphs.sort();
+        glyphs.dedup();
+        for &ch in glyphs.iter() {
+            if let Some(&address) = self.glyph_coords.get(&ch) {
+                if address.row >= self.rows || address.column >= self.columns {
+                    return Err(Error::GlyphOutOfBounds(ch, address, self.rows, self.columns));
+                }
+            }
+            if let Some(rect) = self.glyph_rects.get(&ch) {
+                let inside = rect.x >= 0.0 && rect.y >= 0.0 && rect.width >= 0.0 && rect.height >= 0.0
+                    && rect.x + rect.width <= 1.0 && rect.y + rect.height <= 1.0;
+                if !inside {
+                    return Err(Error::GlyphRectOutOfBounds(ch));
+                }
+            }
+            if !self.glyph_widths.contains_key(&ch) {
+                return Err(Error::MissingGlyphWidth(ch));
+            }
+            if !self.glyph_y_offsets.contains_key(&ch) {
+                return Err(Error::MissingGlyphOffset(ch));
+            }
+        }
+
+        let mut metrics: Vec<char> = self.glyph_widths.keys()
+            .chain(self.glyph_y_offsets.keys())
+            .cloned()
+            .collect();
+        metrics.sort();
+        for &ch in metrics.iter() {
+            if !self.glyph_coords.contains_key(&ch) && !self.glyph_rects.contains_key(&ch) {
+                return Err(Error::MissingGlyphCoords(ch));
+            }
+        }
+
+        if let Some(fallback) = self.fallback_glyph {
+            if !self.has_glyph(fallback) {
+                return Err(Error::MissingFallbackGlyph(fallback));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Kerning pairs are stored in the atlas file as a list of `KerningPair` 
+/// records, since JSON object keys cannot be pairs of characters.
+mod kerning_pairs {
+    use super::KerningPair;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::collections::HashMap;
+
+
+    pub fn serialize<S: Serializer>(
+        kerning_pairs: &HashMap<(char, char), f32>, serializer: S) -> Result<S::Ok, S::Error> {
+
+        let mut pairs: Vec<KerningPair> = kerning_pairs.iter().map(|(&(left, right), &amount)| {
+            KerningPair { left: left, right: right, amount: amount }
+        }).collect();
+        pairs.sort_by_key(|pair| (pair.left, pair.right));
+
+        pairs.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D) -> Result<HashMap<(char, char), f32>, D::Error> {
+
+        let pairs = Vec::<KerningPair>::deserialize(deserializer)?;
+
+        Ok(pairs.iter().map(|pair| ((pair.left, pair.right), pair.amount)).collect())
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -24,12 +223,56 @@ pub enum Error {
     FileNotFound(String),
     CouldNotParseFontFile(String),
     CouldNotParseBuffer,
+    GlyphOutOfBounds(char, Address, usize, usize),
+    GlyphRectOutOfBounds(char),
+    MissingGlyphCoords(char),
+    MissingGlyphWidth(char),
+    MissingGlyphOffset(char),
+    MissingFallbackGlyph(char),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &Error::FileNotFound(ref file_name) => {
+                write!(f, "Could not open the font atlas file for reading: {}", file_name)
+            }
+            &Error::CouldNotParseFontFile(ref file_name) => {
+                write!(f, "The font atlas file exists, but there was an error in parsing it: {}", file_name)
+            }
+            &Error::CouldNotParseBuffer => {
+                write!(f, "Could not parse the font atlas buffer.")
+            }
+            &Error::GlyphOutOfBounds(ch, address, rows, columns) => {
+                write!(f, 
+                    "The glyph {:?} at row {}, column {} lies outside the {}x{} atlas grid.", 
+                    ch, address.row, address.column, rows, columns
+                )
+            }
+            &Error::GlyphRectOutOfBounds(ch) => {
+                write!(f, "The glyph {:?} lies outside the atlas image.", ch)
+            }
+            &Error::MissingGlyphCoords(ch) => {
+                write!(f, "The glyph {:?} has metrics but no coordinates in the atlas.", ch)
+            }
+            &Error::MissingGlyphWidth(ch) => {
+                write!(f, "The glyph {:?} has no width.", ch)
+            }
+            &Error::MissingGlyphOffset(ch) => {
+                write!(f, "The glyph {:?} has no y-offset.", ch)
+            }
+            &Error::MissingFallbackGlyph(ch) => {
+                write!(f, "The fallback glyph {:?} is not in the atlas.", ch)
+            }
+        }
+    }
 }
 
 pub fn load_reader<R: io::Read>(reader: R) -> Result<FontAtlas, Error> {
-    let font_atlas = serde_json::from_reader(reader).map_err(|_e| {
+    let font_atlas: FontAtlas = serde_json::from_reader(reader).map_err(|_e| {
         Error::CouldNotParseBuffer
     })?;
+    font_atlas.validate()?;
 
     Ok(font_atlas)
 }
@@ -45,12 +288,192 @@ pub fn load_file<P: AsRef<Path>>(file: P) -> Result<FontAtlas, Error> {
     };
     let font_atlas = match load_reader(data) {
         Ok(val) => val,
-        Err(_) => {
+        Err(Error::CouldNotParseBuffer) => {
             return Err(
                 Error::CouldNotParseFontFile(format!("{}", file.as_ref().display()))
             );
         }
+        Err(e) => return Err(e),
     };
 
     Ok(font_atlas)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+
+    /// A 2x2 grid atlas with the glyphs 'a', 'b' and the fallback glyph '?'.
+    fn test_atlas() -> FontAtlas {
+        let mut glyph_y_offsets = HashMap::new();
+        let mut glyph_widths = HashMap::new();
+        let mut glyph_coords = HashMap::new();
+        for (i, ch) in "ab?".chars().enumerate() {
+            glyph_y_offsets.insert(ch, 0.0);
+            glyph_widths.insert(ch, 0.5);
+            glyph_coords.insert(ch, Address { row: i / 2, column: i % 2 });
+        }
+
+        FontAtlas {
+            glyph_y_offsets: glyph_y_offsets,
+            glyph_widths: glyph_widths,
+            glyph_coords: glyph_coords,
+            rows: 2,
+            columns: 2,
+            glyph_rects: HashMap::new(),
+            kerning_pairs: HashMap::new(),
+            fallback_glyph: Some('?'),
+            distance_field: None,
+            missing_glyphs: Default::default(),
+        }
+    }
+
+    fn rect(x: f32, y: f32, width: f32, height: f32) -> GlyphRect {
+        GlyphRect { x: x, y: y, width: width, height: height, offset_x: 0.0, quad_width: 1.0, quad_height: 1.0 }
+    }
+
+    #[test]
+    fn test_valid_atlas() {
+        assert!(test_atlas().validate().is_ok());
+    }
+
+    #[test]
+    fn test_glyph_out_of_bounds() {
+        let mut atlas = test_atlas();
+        atlas.glyph_coords.insert('b', Address { row: 0, column: 2 });
+
+        match atlas.validate() {
+            Err(Error::GlyphOutOfBounds('b', _, 2, 2)) => {}
+            other => panic!("expected GlyphOutOfBounds, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_glyph_rect_out_of_bounds() {
+        let mut atlas = test_atlas();
+        atlas.glyph_rects.insert('c', rect(0.75, 0.0, 0.5, 0.5));
+        atlas.glyph_widths.insert('c', 0.5);
+        atlas.glyph_y_offsets.insert('c', 0.0);
+
+        match atlas.validate() {
+            Err(Error::GlyphRectOutOfBounds('c')) => {}
+            other => panic!("expected GlyphRectOutOfBounds, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_missing_glyph_width() {
+        let mut atlas = test_atlas();
+        atlas.glyph_widths.remove(&'a');
+
+        match atlas.validate() {
+            Err(Error::MissingGlyphWidth('a')) => {}
+            other => panic!("expected MissingGlyphWidth, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_missing_glyph_offset() {
+        let mut atlas = test_atlas();
+        atlas.glyph_y_offsets.remove(&'a');
+
+        match atlas.validate() {
+            Err(Error::MissingGlyphOffset('a')) => {}
+            other => panic!("expected MissingGlyphOffset, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_missing_glyph_coords() {
+        let mut atlas = test_atlas();
+        atlas.glyph_widths.insert('z', 0.5);
+
+        match atlas.validate() {
+            Err(Error::MissingGlyphCoords('z')) => {}
+            other => panic!("expected MissingGlyphCoords, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_missing_fallback_glyph() {
+        let mut atlas = test_atlas();
+        atlas.fallback_glyph = Some('#');
+
+        match atlas.validate() {
+            Err(Error::MissingFallbackGlyph('#')) => {}
+            other => panic!("expected MissingFallbackGlyph, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_load_reader_rejects_malformed_json() {
+        match load_reader("{\"rows\": 2".as_bytes()) {
+            Err(Error::CouldNotParseBuffer) => {}
+            other => panic!("expected CouldNotParseBuffer, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_load_reader_validates_the_atlas() {
+        let mut atlas = test_atlas();
+        atlas.fallback_glyph = Some('#');
+        let json = serde_json::to_string(&atlas).unwrap();
+
+        match load_reader(json.as_bytes()) {
+            Err(Error::MissingFallbackGlyph('#')) => {}
+            other => panic!("expected MissingFallbackGlyph, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_resolve_glyph_in_the_atlas() {
+        let atlas = test_atlas();
+
+        assert_eq!(atlas.resolve_glyph('a'), Some('a'));
+        assert!(atlas.missing_glyphs.borrow().is_empty());
+    }
+
+    #[test]
+    fn test_resolve_glyph_substitutes_the_fallback() {
+        let atlas = test_atlas();
+
+        assert_eq!(atlas.resolve_glyph('z'), Some('?'));
+        assert_eq!(atlas.resolve_glyph('z'), Some('?'));
+        assert_eq!(atlas.resolve_glyph('y'), Some('?'));
+        assert_eq!(atlas.missing_glyphs.borrow().len(), 2);
+    }
+
+    #[test]
+    fn test_resolve_glyph_without_fallback_skips() {
+        let mut atlas = test_atlas();
+        atlas.fallback_glyph = None;
+
+        assert_eq!(atlas.resolve_glyph('z'), None);
+        assert!(atlas.missing_glyphs.borrow().contains(&'z'));
+    }
+
+    #[test]
+    fn test_atlas_without_kerning_loads() {
+        // The atlas shipped with the demo predates kerning.
+        let json = include_str!("../assets/text_font2048x2048.json");
+        let atlas = load_reader(json.as_bytes()).unwrap();
+
+        assert!(atlas.kerning_pairs.is_empty());
+        assert_eq!(atlas.kerning('A', 'V'), 0.0);
+    }
+
+    #[test]
+    fn test_kerning_pairs_round_trip() {
+        let mut atlas = test_atlas();
+        atlas.kerning_pairs.insert(('a', 'b'), -0.125);
+        atlas.kerning_pairs.insert(('b', '?'), 0.25);
+        let json = serde_json::to_string(&atlas).unwrap();
+        let result = load_reader(json.as_bytes()).unwrap();
+
+        assert!(json.contains("\"kerning_pairs\":[{\"left\":\"a\",\"right\":\"b\",\"amount\":-0.125}"));
+        assert_eq!(result.kerning_pairs, atlas.kerning_pairs);
+        assert_eq!(result.kerning('a', 'b'), -0.125);
+        assert_eq!(result.kerning('b', 'a'), 0.0);
+    }
+}
@@ -0,0 +1,285 @@
+#![allow(dead_code)]
+use cglinalg::{
+    Vector3,
+    Matrix4,
+};
+
+
+/// A plane in world space. Points on the side the normal faces have positive
+/// signed distance from the plane.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Plane {
+    pub normal: Vector3<f32>,
+    pub distance: f32,
+}
+
+impl Plane {
+    pub fn new(normal: Vector3<f32>, distance: f32) -> Plane {
+        Plane {
+            normal: normal,
+            distance: distance,
+        }
+    }
+
+    /// Build a plane from the coefficients of the plane equation `ax + by + cz + d = 0`,
+    /// scaled so the normal has unit length.
+    fn from_coefficients(a: f32, b: f32, c: f32, d: f32) -> Plane {
+        let normal = Vector3::new(a, b, c);
+        let length = normal.magnitude();
+
+        Plane::new(normal * (1.0 / length), d / length)
+    }
+
+    /// The signed distance from the plane to `point`.
+    #[inline]
+    pub fn signed_distance(&self, point: &Vector3<f32>) -> f32 {
+        self.normal.dot(point) + self.distance
+    }
+}
+
+/// An axis aligned bounding box in world space.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: Vector3<f32>,
+    pub max: Vector3<f32>,
+}
+
+impl Aabb {
+    pub fn new(min: Vector3<f32>, max: Vector3<f32>) -> Aabb {
+        Aabb {
+            min: min,
+            max: max,
+        }
+    }
+
+    /// The smallest box containing all of `points`.
+    pub fn from_points(points: &[[f32; 3]]) -> Aabb {
+        let mut min = Vector3::new(f32::MAX, f32::MAX, f32::MAX);
+        let mut max = Vector3::new(f32::MIN, f32::MIN, f32::MIN);
+        for point in points.iter() {
+            min.x = f32::min(min.x, point[0]);
+            min.y = f32::min(min.y, point[1]);
+            min.z = f32::min(min.z, point[2]);
+            max.x = f32::max(max.x, point[0]);
+            max.y = f32::max(max.y, point[1]);
+            max.z = f32::max(max.z, point[2]);
+        }
+
+        Aabb::new(min, max)
+    }
+
+    #[inline]
+    pub fn center(&self) -> Vector3<f32> {
+        (self.min + self.max) * 0.5
+    }
+
+    /// The distances from the center of the box to its faces.
+    #[inline]
+    pub fn half_extents(&self) -> Vector3<f32> {
+        (self.max - self.min) * 0.5
+    }
+}
+
+/// A bounding sphere in world space.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Sphere {
+    pub center: Vector3<f32>,
+    pub radius: f32,
+}
+
+impl Sphere {
+    pub fn new(center: Vector3<f32>, radius: f32) -> Sphere {
+        Sphere {
+            center: center,
+            radius: radius,
+        }
+    }
+}
+
+/// The six planes bounding the region of the world a camera can see. The plane
+/// normals all face into the frustum.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Frustum {
+    pub left: Plane,
+    pub right: Plane,
+    pub bottom: Plane,
+    pub top: Plane,
+    pub near: Plane,
+    pub far: Plane,
+}
+
+impl Frustum {
+    /// Extract the frustum planes from a combined projection and view matrix. A point
+    /// is inside the frustum when its clip coordinates satisfy `-w <= x, y, z <= w`,
+    /// and each of those inequalities is a plane in world space made from sums and
+    /// differences of the rows of the matrix.
+    pub fn from_matrix(proj_view_mat: &Matrix4<f32>) -> Frustum {
+        let m = proj_view_mat;
+        let row = |i: usize| [m[0][i], m[1][i], m[2][i], m[3][i]];
+        let (x, y, z, w) = (row(0), row(1), row(2), row(3));
+        let plus = |r: [f32; 4]| Plane::from_coefficients(w[0] + r[0], w[1] + r[1], w[2] + r[2], w[3] + r[3]);
+        let minus = |r: [f32; 4]| Plane::from_coefficients(w[0] - r[0], w[1] - r[1], w[2] - r[2], w[3] - r[3]);
+
+        Frustum {
+            left: plus(x),
+            right: minus(x),
+            bottom: plus(y),
+            top: minus(y),
+            near: plus(z),
+            far: minus(z),
+        }
+    }
+
+    #[inline]
+    pub fn planes(&self) -> [Plane; 6] {
+        [self.left, self.right, self.bottom, self.top, self.near, self.far]
+    }
+
+    pub fn contains_point(&self, point: &Vector3<f32>) -> bool {
+        self.planes().iter().all(|plane| plane.signed_distance(point) >= 0.0)
+    }
+
+    /// Determine whether any part of a sphere may lie inside the frustum.
+    pub fn intersects_sphere(&self, sphere: &Sphere) -> bool {
+        self.planes().iter().all(|plane| plane.signed_distance(&sphere.center) >= -sphere.radius)
+    }
+
+    /// Determine whether any part of a box may lie inside the frustum. A box is
+    /// only rejected when it lies entirely behind one of the planes, so a few
+    /// boxes near the corners of the frustum pass without being visible.
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        let center = aabb.center();
+        let half_extents = aabb.half_extents();
+        self.planes().iter().all(|plane| {
+            let radius = half_extents.x * f32::abs(plane.normal.x)
+                + half_extents.y * f32::abs(plane.normal.y)
+                + half_extents.z * f32::abs(plane.normal.z);
+
+            plane.signed_distance(&center) >= -radius
+        })
+    }
+}
+
+/// Counts of the objects tested against the view frustum in a frame, for debugging.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct CullingStats {
+    pub tested: u32,
+    pub culled: u32,
+}
+
+impl CullingStats {
+    pub fn new() -> CullingStats {
+        CullingStats {
+            tested: 0,
+            culled: 0,
+        }
+    }
+
+    /// Record whether an object was visible, returning the visibility so the test
+    /// can be wrapped around the check that decides whether to draw.
+    #[inline]
+    pub fn record(&mut self, visible: bool) -> bool {
+        self.tested += 1;
+        if !visible {
+            self.culled += 1;
+        }
+
+        visible
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cglinalg::Degrees;
+
+
+    /// The view frustum of a camera at `cam_pos` looking down the negative z-axis.
+    fn perspective_frustum(cam_pos: Vector3<f32>) -> Frustum {
+        let proj_mat = Matrix4::from_perspective_fov(Degrees(67.0), 1.0, 0.1, 100.0);
+        let view_mat = Matrix4::from_affine_translation(&(-cam_pos));
+
+        Frustum::from_matrix(&(proj_mat * view_mat))
+    }
+
+    /// A box shaped frustum spanning `[-1, 1]` in x and y, looking down the negative z-axis.
+    fn orthographic_frustum() -> Frustum {
+        let proj_mat = Matrix4::from_orthographic(-1.0, 1.0, -1.0, 1.0, 0.1, 100.0);
+
+        Frustum::from_matrix(&proj_mat)
+    }
+
+    #[test]
+    fn test_frustum_contains_points_in_front_of_camera() {
+        let frustum = perspective_frustum(Vector3::new(1.0, 2.0, 3.0));
+
+        assert!(frustum.contains_point(&Vector3::new(1.0, 2.0, -2.0)));
+        assert!(!frustum.contains_point(&Vector3::new(1.0, 2.0, 8.0)));
+        assert!(!frustum.contains_point(&Vector3::new(30.0, 2.0, -2.0)));
+        assert!(!frustum.contains_point(&Vector3::new(1.0, 2.0, -200.0)));
+    }
+
+    #[test]
+    fn test_frustum_planes_face_into_the_frustum() {
+        let frustum = perspective_frustum(Vector3::new(0.0, 0.0, 0.0));
+        let inside = Vector3::new(0.0, 0.0, -10.0);
+
+        for plane in frustum.planes().iter() {
+            assert!((plane.normal.magnitude() - 1.0).abs() < 1e-4);
+            assert!(plane.signed_distance(&inside) > 0.0);
+        }
+    }
+
+    #[test]
+    fn test_frustum_culls_volumes_outside_the_view() {
+        let frustum = perspective_frustum(Vector3::new(0.0, 0.0, 0.0));
+
+        assert!(frustum.intersects_sphere(&Sphere::new(Vector3::new(0.0, 0.0, -10.0), 1.0)));
+        assert!(!frustum.intersects_sphere(&Sphere::new(Vector3::new(0.0, 0.0, 10.0), 1.0)));
+        // A box straddling the near plane is still visible.
+        let straddling = Aabb::new(Vector3::new(-1.0, -1.0, -1.0), Vector3::new(1.0, 1.0, 1.0));
+        assert!(frustum.intersects_aabb(&straddling));
+        let beside = Aabb::new(Vector3::new(20.0, -1.0, -6.0), Vector3::new(22.0, 1.0, -4.0));
+        assert!(!frustum.intersects_aabb(&beside));
+    }
+
+    #[test]
+    fn test_frustum_accepts_a_box_touching_a_plane() {
+        let frustum = orthographic_frustum();
+        let touching_left = Aabb::new(Vector3::new(-2.0, -0.5, -6.0), Vector3::new(-1.0, 0.5, -4.0));
+        let touching_top = Aabb::new(Vector3::new(-0.5, 1.0, -6.0), Vector3::new(0.5, 2.0, -4.0));
+        let past_left = Aabb::new(Vector3::new(-2.0, -0.5, -6.0), Vector3::new(-1.001, 0.5, -4.0));
+
+        assert!(frustum.intersects_aabb(&touching_left));
+        assert!(frustum.intersects_aabb(&touching_top));
+        assert!(!frustum.intersects_aabb(&past_left));
+    }
+
+    #[test]
+    fn test_frustum_accepts_a_sphere_touching_a_plane() {
+        let frustum = orthographic_frustum();
+
+        assert!(frustum.intersects_sphere(&Sphere::new(Vector3::new(3.0, 0.0, -5.0), 2.0)));
+        assert!(!frustum.intersects_sphere(&Sphere::new(Vector3::new(3.0, 0.0, -5.0), 1.999)));
+    }
+
+    #[test]
+    fn test_aabb_from_points_bounds_every_point() {
+        let aabb = Aabb::from_points(&[[1.0, -2.0, 3.0], [-4.0, 5.0, 0.0], [2.0, 1.0, -6.0]]);
+
+        assert_eq!(aabb.min, Vector3::new(-4.0, -2.0, -6.0));
+        assert_eq!(aabb.max, Vector3::new(2.0, 5.0, 3.0));
+        assert_eq!(aabb.center(), Vector3::new(-1.0, 1.5, -1.5));
+        assert_eq!(aabb.half_extents(), Vector3::new(3.0, 3.5, 4.5));
+    }
+
+    #[test]
+    fn test_culling_stats_count_culled_objects() {
+        let mut stats = CullingStats::new();
+
+        assert!(stats.record(true));
+        assert!(!stats.record(false));
+        assert!(!stats.record(false));
+        assert_eq!(stats, CullingStats { tested: 3, culled: 2 });
+    }
+}
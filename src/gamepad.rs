@@ -0,0 +1,507 @@
+#![allow(dead_code)]
+use glfw::{Glfw, Joystick, JoystickId};
+use crate::camera::CameraInput;
+
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::os::raw::c_int;
+use std::path::Path;
+
+use log::info;
+
+
+const JOYSTICK_IDS: [JoystickId; 16] = [
+    JoystickId::Joystick1,  JoystickId::Joystick2,  JoystickId::Joystick3,  JoystickId::Joystick4,
+    JoystickId::Joystick5,  JoystickId::Joystick6,  JoystickId::Joystick7,  JoystickId::Joystick8,
+    JoystickId::Joystick9,  JoystickId::Joystick10, JoystickId::Joystick11, JoystickId::Joystick12,
+    JoystickId::Joystick13, JoystickId::Joystick14, JoystickId::Joystick15, JoystickId::Joystick16,
+];
+
+/// How an analog control turns a raw deflection into input. Sticks rarely rest at
+/// exactly zero, so deflections inside the dead zone read as zero, and the rest of
+/// the range is stretched to start from zero at the edge of the dead zone. The
+/// response curve then raises the deflection to a power, so values above one give
+/// finer control near the center of the stick and full speed at the edge.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct StickResponse {
+    /// The fraction of full deflection that reads as zero.
+    pub dead_zone: f32,
+    /// The exponent of the response curve. An exponent of one is linear.
+    pub exponent: f32,
+}
+
+impl StickResponse {
+    pub const fn new(dead_zone: f32, exponent: f32) -> StickResponse {
+        StickResponse { dead_zone: dead_zone, exponent: exponent }
+    }
+
+    #[inline]
+    fn curve(&self, magnitude: f32) -> f32 {
+        let magnitude = f32::min(magnitude, 1.0);
+        if magnitude <= self.dead_zone {
+            return 0.0;
+        }
+
+        f32::powf((magnitude - self.dead_zone) / (1.0 - self.dead_zone), self.exponent)
+    }
+
+    /// Apply the response to a single axis in the range `[-1, 1]`.
+    pub fn apply_axis(&self, value: f32) -> f32 {
+        f32::signum(value) * self.curve(f32::abs(value))
+    }
+
+    /// Apply the response to both axes of a stick. The dead zone is round, so
+    /// pushing the stick along a diagonal does not snap to either axis.
+    pub fn apply(&self, x: f32, y: f32) -> (f32, f32) {
+        let magnitude = f32::sqrt(x * x + y * y);
+        let response = self.curve(magnitude);
+        if response == 0.0 {
+            return (0.0, 0.0);
+        }
+
+        (x / magnitude * response, y / magnitude * response)
+    }
+}
+
+/// The indices of the controls of a gamepad in the raw axis and button arrays
+/// GLFW reports for it. Different controllers, drivers, and platforms report the
+/// same controls differently, so the layout can be loaded from a file.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GamepadLayout {
+    pub left_x: usize,
+    pub left_y: usize,
+    pub right_x: usize,
+    pub right_y: usize,
+    pub left_trigger: usize,
+    pub right_trigger: usize,
+    pub left_shoulder: usize,
+    pub right_shoulder: usize,
+    pub start: usize,
+    /// Whether the stick y-axes read positive when the stick is pushed up.
+    pub y_axes_up: bool,
+}
+
+/// The layout of an Xbox controller on Windows.
+pub const XINPUT_LAYOUT: GamepadLayout = GamepadLayout {
+    left_x: 0, left_y: 1, right_x: 2, right_y: 3, left_trigger: 4, right_trigger: 5,
+    left_shoulder: 4, right_shoulder: 5, start: 7, y_axes_up: true,
+};
+
+/// The layout of an Xbox controller using the Linux kernel driver.
+pub const LINUX_LAYOUT: GamepadLayout = GamepadLayout {
+    left_x: 0, left_y: 1, right_x: 3, right_y: 4, left_trigger: 2, right_trigger: 5,
+    left_shoulder: 4, right_shoulder: 5, start: 7, y_axes_up: false,
+};
+
+impl GamepadLayout {
+    /// The layout of an Xbox controller with the default driver on the platform
+    /// the demo runs on. Other controllers need a layout file.
+    pub fn native() -> GamepadLayout {
+        if cfg!(target_os = "linux") {
+            LINUX_LAYOUT
+        } else {
+            XINPUT_LAYOUT
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Error {
+    FileNotFound(String),
+    CouldNotParseLayoutFile(String),
+    CouldNotParseBuffer,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &Error::FileNotFound(ref file_name) => {
+                write!(f, "Could not open the gamepad layout file for reading: {}", file_name)
+            }
+            &Error::CouldNotParseLayoutFile(ref file_name) => {
+                write!(f, "The gamepad layout file exists, but there was an error in parsing it: {}", file_name)
+            }
+            &Error::CouldNotParseBuffer => {
+                write!(f, "Could not parse the gamepad layout buffer.")
+            }
+        }
+    }
+}
+
+pub fn load_reader<R: io::Read>(reader: R) -> Result<GamepadLayout, Error> {
+    serde_json::from_reader(reader).map_err(|_e| {
+        Error::CouldNotParseBuffer
+    })
+}
+
+pub fn load_file<P: AsRef<Path>>(file: P) -> Result<GamepadLayout, Error> {
+    let data = match File::open(file.as_ref()) {
+        Ok(handle) => handle,
+        Err(_) => {
+            return Err(
+                Error::FileNotFound(format!("{}", file.as_ref().display()))
+            );
+        }
+    };
+    let layout = match load_reader(data) {
+        Ok(val) => val,
+        Err(Error::CouldNotParseBuffer) => {
+            return Err(
+                Error::CouldNotParseLayoutFile(format!("{}", file.as_ref().display()))
+            );
+        }
+        Err(e) => return Err(e),
+    };
+
+    Ok(layout)
+}
+
+/// The controllers plugged into the machine. The first connected controller drives
+/// the camera. Controllers can be plugged in and pulled out while the demo runs.
+pub struct Gamepads {
+    joysticks: Vec<Joystick>,
+    connected: Vec<bool>,
+    active: Option<usize>,
+    axes: Vec<f32>,
+    buttons: Vec<c_int>,
+    pub layout: GamepadLayout,
+    pub stick_response: StickResponse,
+    pub trigger_response: StickResponse,
+}
+
+impl Gamepads {
+    pub fn new(glfw: &Glfw, layout: GamepadLayout, stick_response: StickResponse, trigger_response: StickResponse) -> Gamepads {
+        let joysticks: Vec<Joystick> = JOYSTICK_IDS.iter().map(|&id| glfw.get_joystick(id)).collect();
+        let connected = vec![false; joysticks.len()];
+
+        Gamepads {
+            joysticks: joysticks,
+            connected: connected,
+            active: None,
+            axes: vec![],
+            buttons: vec![],
+            layout: layout,
+            stick_response: stick_response,
+            trigger_response: trigger_response,
+        }
+    }
+
+    /// Check for controllers plugged in or pulled out since the last update, and
+    /// read the state of the active controller. Call this once per frame after
+    /// polling for events.
+    pub fn update(&mut self) {
+        for (i, joystick) in self.joysticks.iter().enumerate() {
+            let present = joystick.is_present();
+            if present && !self.connected[i] {
+                info!("Gamepad connected: {:?} \"{}\"", joystick.id, joystick.get_name());
+            } else if !present && self.connected[i] {
+                info!("Gamepad disconnected: {:?}", joystick.id);
+            }
+            self.connected[i] = present;
+        }
+
+        let active = self.connected.iter().position(|&present| present);
+        if active != self.active {
+            if let Some(i) = active {
+                info!("Using gamepad {:?} for camera input.", self.joysticks[i].id);
+            }
+            self.active = active;
+        }
+
+        match self.active {
+            Some(i) => {
+                self.axes = self.joysticks[i].get_axes();
+                self.buttons = self.joysticks[i].get_buttons();
+            }
+            None => {
+                self.axes.clear();
+                self.buttons.clear();
+            }
+        }
+    }
+
+    /// Determine whether any controller is connected.
+    pub fn is_connected(&self) -> bool {
+        self.active.is_some()
+    }
+
+    #[inline]
+    fn axis(&self, index: usize) -> f32 {
+        self.axes.get(index).cloned().unwrap_or(0.0)
+    }
+
+    #[inline]
+    fn button(&self, index: usize) -> bool {
+        self.buttons.get(index).map_or(false, |&state| state != 0)
+    }
+
+    /// Read a stick with the y-axis pointing up.
+    #[inline]
+    fn stick(&self, x_index: usize, y_index: usize) -> (f32, f32) {
+        let y = if self.layout.y_axes_up { self.axis(y_index) } else { -self.axis(y_index) };
+
+        self.stick_response.apply(self.axis(x_index), y)
+    }
+
+    /// Read a trigger. Triggers rest at -1 and read 1 when pulled all the way.
+    #[inline]
+    fn trigger(&self, index: usize) -> f32 {
+        if index >= self.axes.len() {
+            return 0.0;
+        }
+
+        self.trigger_response.apply_axis(0.5 * (self.axis(index) + 1.0))
+    }
+
+    /// Determine whether the start button is held down.
+    pub fn is_start_pressed(&self) -> bool {
+        self.button(self.layout.start)
+    }
+
+    /// Add the controller input to the keyboard input for the camera. The left
+    /// stick moves the camera, the right stick turns it, the triggers move it down
+    /// and up, and the shoulder buttons roll it.
+    pub fn camera_input(&self, input: &mut CameraInput) {
+        if self.active.is_none() {
+            return;
+        }
+
+        let (left_x, left_y) = self.stick(self.layout.left_x, self.layout.left_y);
+        let (right_x, right_y) = self.stick(self.layout.right_x, self.layout.right_y);
+        let lift = self.trigger(self.layout.right_trigger) - self.trigger(self.layout.left_trigger);
+        let mut roll = 0.0;
+        if self.button(self.layout.left_shoulder) {
+            roll -= 1.0;
+        }
+        if self.button(self.layout.right_shoulder) {
+            roll += 1.0;
+        }
+
+        input.move_x = clamp_unit(input.move_x + left_x);
+        input.move_y = clamp_unit(input.move_y + lift);
+        input.move_z = clamp_unit(input.move_z - left_y);
+        input.yaw = clamp_unit(input.yaw - right_x);
+        input.pitch = clamp_unit(input.pitch + right_y);
+        input.roll = clamp_unit(input.roll + roll);
+    }
+}
+
+#[inline]
+fn clamp_unit(value: f32) -> f32 {
+    f32::max(-1.0, f32::min(value, 1.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+
+    const EPSILON: f32 = 1e-4;
+    const RESPONSE: StickResponse = StickResponse::new(0.25, 2.0);
+
+    fn assert_f32_eq(result: f32, expected: f32) {
+        assert!(f32::abs(result - expected) < EPSILON, "expected {}, got {}", expected, result);
+    }
+
+    #[test]
+    fn test_inside_dead_zone_reads_zero() {
+        for &value in [0.0, 0.1, -0.1, 0.25, -0.25].iter() {
+            assert_f32_eq(RESPONSE.apply_axis(value), 0.0);
+        }
+        assert_eq!(RESPONSE.apply(0.1, -0.2), (0.0, 0.0));
+    }
+
+    #[test]
+    fn test_full_deflection_reads_one() {
+        assert_f32_eq(RESPONSE.apply_axis(1.0), 1.0);
+        assert_f32_eq(RESPONSE.apply_axis(-1.0), -1.0);
+
+        let (x, y) = RESPONSE.apply(1.0, 0.0);
+        assert_f32_eq(x, 1.0);
+        assert_f32_eq(y, 0.0);
+        let (x, y) = RESPONSE.apply(0.0, -1.0);
+        assert_f32_eq(x, 0.0);
+        assert_f32_eq(y, -1.0);
+    }
+
+    #[test]
+    fn test_overshoot_is_clamped() {
+        assert_f32_eq(RESPONSE.apply_axis(1.5), 1.0);
+        assert_f32_eq(RESPONSE.apply_axis(-1.5), -1.0);
+    }
+
+    #[test]
+    fn test_response_never_decreases() {
+        for &response in [RESPONSE, StickResponse::new(0.1, 1.0), StickResponse::new(0.0, 0.5)].iter() {
+            let mut last = response.apply_axis(0.0);
+            for step in 1..=100 {
+                let value = response.apply_axis(step as f32 / 100.0);
+                assert!(value >= last, "response decreased from {} to {} at step {}", last, value, step);
+                last = value;
+            }
+        }
+    }
+
+    #[test]
+    fn test_response_curve() {
+        // Halfway between the edge of the dead zone and full deflection.
+        assert_f32_eq(RESPONSE.apply_axis(0.625), 0.25);
+        assert_f32_eq(StickResponse::new(0.25, 1.0).apply_axis(-0.625), -0.5);
+    }
+
+    #[test]
+    fn test_radial_dead_zone_passes_diagonals() {
+        // Each axis is inside the dead zone, but the stick as a whole is outside it.
+        assert_f32_eq(RESPONSE.apply_axis(0.2), 0.0);
+        let (x, y) = RESPONSE.apply(0.2, 0.2);
+        assert!(x > 0.0 && y > 0.0);
+        assert_f32_eq(x, y);
+    }
+
+    #[test]
+    fn test_radial_dead_zone_keeps_direction() {
+        // Applying the response to each axis separately snaps a shallow push onto
+        // the x-axis, while the radial response keeps the small y component.
+        assert_f32_eq(RESPONSE.apply_axis(0.1), 0.0);
+        let (x, y) = RESPONSE.apply(0.8, 0.1);
+        assert!(y > 0.0);
+        assert_f32_eq(y / x, 0.1 / 0.8);
+    }
+
+    /// An active gamepad reporting the raw `axes` and `buttons` in the layout `layout`,
+    /// with linear responses and no trigger dead zone.
+    fn fake_gamepad(layout: GamepadLayout, axes: &[f32], buttons: &[c_int]) -> Gamepads {
+        Gamepads {
+            joysticks: vec![],
+            connected: vec![],
+            active: Some(0),
+            axes: axes.to_vec(),
+            buttons: buttons.to_vec(),
+            layout: layout,
+            stick_response: StickResponse::new(0.2, 1.0),
+            trigger_response: StickResponse::new(0.0, 1.0),
+        }
+    }
+
+    /// The raw axes of an Xbox controller on Linux at rest: sticks centered, triggers released.
+    const LINUX_AT_REST: [f32; 6] = [0.0, 0.0, -1.0, 0.0, 0.0, -1.0];
+    const NO_BUTTONS: [c_int; 8] = [0; 8];
+
+    fn camera_input(gamepads: &Gamepads) -> CameraInput {
+        let mut input = CameraInput::new();
+        gamepads.camera_input(&mut input);
+
+        input
+    }
+
+    #[test]
+    fn test_gamepad_at_rest_gives_no_input() {
+        let gamepads = fake_gamepad(LINUX_LAYOUT, &LINUX_AT_REST, &NO_BUTTONS);
+
+        assert_eq!(camera_input(&gamepads), CameraInput::new());
+        assert!(!gamepads.is_start_pressed());
+    }
+
+    #[test]
+    fn test_sticks_move_and_turn_the_camera() {
+        let mut axes = LINUX_AT_REST;
+        axes[LINUX_LAYOUT.left_x] = 1.0;
+        axes[LINUX_LAYOUT.right_y] = -1.0;
+        let input = camera_input(&fake_gamepad(LINUX_LAYOUT, &axes, &NO_BUTTONS));
+
+        assert_f32_eq(input.move_x, 1.0);
+        assert_f32_eq(input.move_z, 0.0);
+        assert_f32_eq(input.yaw, 0.0);
+        // Pushing the right stick up pitches the camera up.
+        assert_f32_eq(input.pitch, 1.0);
+    }
+
+    #[test]
+    fn test_layouts_agree_on_the_direction_of_the_y_axes() {
+        // Push the left stick all the way up on each platform.
+        let mut linux_axes = LINUX_AT_REST;
+        linux_axes[LINUX_LAYOUT.left_y] = -1.0;
+        let mut xinput_axes = [0.0, 0.0, 0.0, 0.0, -1.0, -1.0];
+        xinput_axes[XINPUT_LAYOUT.left_y] = 1.0;
+        let linux = camera_input(&fake_gamepad(LINUX_LAYOUT, &linux_axes, &NO_BUTTONS));
+        let xinput = camera_input(&fake_gamepad(XINPUT_LAYOUT, &xinput_axes, &NO_BUTTONS));
+
+        assert_f32_eq(linux.move_z, -1.0);
+        assert_eq!(linux, xinput);
+    }
+
+    #[test]
+    fn test_triggers_are_normalized_from_rest() {
+        let mut axes = LINUX_AT_REST;
+        axes[LINUX_LAYOUT.right_trigger] = 1.0;
+        assert_f32_eq(camera_input(&fake_gamepad(LINUX_LAYOUT, &axes, &NO_BUTTONS)).move_y, 1.0);
+
+        axes[LINUX_LAYOUT.right_trigger] = 0.0;
+        assert_f32_eq(camera_input(&fake_gamepad(LINUX_LAYOUT, &axes, &NO_BUTTONS)).move_y, 0.5);
+
+        axes[LINUX_LAYOUT.left_trigger] = 1.0;
+        assert_f32_eq(camera_input(&fake_gamepad(LINUX_LAYOUT, &axes, &NO_BUTTONS)).move_y, -0.5);
+    }
+
+    #[test]
+    fn test_shoulder_buttons_roll_the_camera() {
+        let mut buttons = NO_BUTTONS;
+        buttons[LINUX_LAYOUT.left_shoulder] = 1;
+        assert_f32_eq(camera_input(&fake_gamepad(LINUX_LAYOUT, &LINUX_AT_REST, &buttons)).roll, -1.0);
+
+        buttons[LINUX_LAYOUT.right_shoulder] = 1;
+        assert_f32_eq(camera_input(&fake_gamepad(LINUX_LAYOUT, &LINUX_AT_REST, &buttons)).roll, 0.0);
+    }
+
+    #[test]
+    fn test_start_button() {
+        let mut buttons = NO_BUTTONS;
+        buttons[LINUX_LAYOUT.start] = 1;
+
+        assert!(fake_gamepad(LINUX_LAYOUT, &LINUX_AT_REST, &buttons).is_start_pressed());
+    }
+
+    #[test]
+    fn test_gamepad_input_adds_to_keyboard_input_and_is_clamped() {
+        let mut axes = LINUX_AT_REST;
+        axes[LINUX_LAYOUT.left_x] = 1.0;
+        let gamepads = fake_gamepad(LINUX_LAYOUT, &axes, &NO_BUTTONS);
+        let mut input = CameraInput::new();
+        input.move_x = 1.0;
+        input.yaw = 0.5;
+        gamepads.camera_input(&mut input);
+
+        assert_f32_eq(input.move_x, 1.0);
+        assert_f32_eq(input.yaw, 0.5);
+    }
+
+    #[test]
+    fn test_missing_controls_read_as_released() {
+        let gamepads = fake_gamepad(LINUX_LAYOUT, &[0.5], &[]);
+        let input = camera_input(&gamepads);
+
+        assert_f32_eq(input.move_x, 0.375);
+        assert_f32_eq(input.move_y, 0.0);
+        assert!(!gamepads.is_start_pressed());
+    }
+
+    #[test]
+    fn test_no_gamepad_gives_no_input() {
+        let mut gamepads = fake_gamepad(LINUX_LAYOUT, &[1.0; 6], &[1; 8]);
+        gamepads.active = None;
+
+        assert_eq!(camera_input(&gamepads), CameraInput::new());
+    }
+
+    #[test]
+    fn test_load_layout() {
+        let json = serde_json::to_string(&XINPUT_LAYOUT).unwrap();
+
+        assert_eq!(load_reader(json.as_bytes()).unwrap(), XINPUT_LAYOUT);
+        match load_reader("{\"left_x\": 0}".as_bytes()) {
+            Err(Error::CouldNotParseBuffer) => {}
+            other => panic!("expected CouldNotParseBuffer, got {:?}", other),
+        }
+    }
+}
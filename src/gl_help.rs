@@ -221,6 +221,8 @@ pub fn start_gl(width: u32, height: u32) -> Result<GLState, String> {
     window.set_size_polling(true);
     window.set_refresh_polling(true);
     window.set_size_polling(true);
+    window.set_scroll_polling(true);
+    window.set_mouse_button_polling(true);
 
     // Load the OpenGl function pointers.
     gl::load_with(|symbol| { window.get_proc_address(symbol) as *const _ });
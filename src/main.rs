@@ -6,6 +6,7 @@ extern crate serde;
 extern crate serde_json;
 extern crate log;
 extern crate file_logger;
+extern crate unicode_segmentation;
 
 #[macro_use]
 extern crate serde_derive;
@@ -18,13 +19,26 @@ mod gl {
 mod macros;
 
 mod font_atlas;
+mod bmfont;
 mod gl_help;
 mod camera;
+mod camera_bookmarks;
+mod camera_path;
+mod frustum;
+mod ray;
+mod gamepad;
+mod text;
+mod text_buffer;
+mod text_animation;
 
 use glfw::{
     Action, 
     Context, 
-    Key
+    CursorMode,
+    Key,
+    Modifiers,
+    MouseButton,
+    WindowEvent,
 };
 use gl::types::{
     GLenum, 
@@ -39,20 +53,36 @@ use std::mem;
 use std::ptr;
 use std::process;
 
-use font_atlas::FontAtlas;
+use font_atlas::{DistanceField, FontAtlas};
+use text::{Anchor, LayoutParams, Outline, Placement, Shadow, TextBounds, TextEffects};
+use text_animation::TextAnimation;
+use text_buffer::{TextBatch, TextBuffer};
+use camera_bookmarks::CameraBookmarks;
+use camera_path::{CameraPath, CameraPathPlayer};
+use frustum::{Aabb, CullingStats, Plane};
+use gamepad::{GamepadLayout, Gamepads, StickResponse};
 
 use gl_help as glh;
 use cglinalg::{
-    Degrees, 
-    Matrix4, 
     Quaternion, 
     Vector3,
     Vector4,
-    Unit,
 };
-use camera::Camera;
+use camera::{
+    Camera,
+    CameraConstraints,
+    CameraController,
+    CameraInput,
+    CameraMotion,
+    FreeFlyController,
+    MouseLook,
+    OrbitController,
+    Projection,
+    WalkController,
+};
 use log::{
-    info
+    info,
+    error,
 };
 use teximage2d::TexImage2D;
 
@@ -67,9 +97,76 @@ const LOG_FILE: &str = "/tmp/metroid-demo.log";
 #[cfg(not(feature = "build_for_install"))]
 const LOG_FILE: &str = "metroid-demo.log";
 
+#[cfg(feature = "build_for_install")]
+const BOOKMARKS_FILE: &str = "/tmp/metroid-demo-bookmarks.json";
+
+#[cfg(not(feature = "build_for_install"))]
+const BOOKMARKS_FILE: &str = "metroid-demo-bookmarks.json";
+
+#[cfg(feature = "build_for_install")]
+const GAMEPAD_LAYOUT_FILE: &str = "/tmp/metroid-demo-gamepad.json";
+
+#[cfg(not(feature = "build_for_install"))]
+const GAMEPAD_LAYOUT_FILE: &str = "metroid-demo-gamepad.json";
+
+// Title screen text.
+const TITLE_TEXT: &str = "LAMBDAXYMOX";
+const PROMPT_TEXT: &str = "Press [color=#FFD75F]ENTER[/color] to continue";
+// The vertical gap between the title and the prompt in pixels.
+const TITLE_PROMPT_GAP_PX: f32 = 48.0;
+
+// Type out the prompt, then blink it like the classic title screen once it is
+// all on the screen.
+const PROMPT_CHARS_PER_SECOND: f32 = 40.0;
+const PROMPT_TYPEWRITER: TextAnimation = TextAnimation::Typewriter { chars_per_second: PROMPT_CHARS_PER_SECOND };
+const PROMPT_BLINK: TextAnimation = TextAnimation::Blink { period_seconds: 1.0, duty: 0.6 };
+
+// Mouse controls.
+const MOUSE_LOOK: MouseLook = MouseLook::new(0.1, false, Some(85.0));
+// The range of the mouse wheel zoom, and the change per notch of the wheel, in degrees.
+const MIN_FOV: f32 = 20.0;
+const MAX_FOV: f32 = 90.0;
+const FOV_ZOOM_STEP: f32 = 2.0;
+// The range of the orthographic zoom in world units, and the change in the view
+// height per notch of the wheel.
+const ORTHOGRAPHIC_HEIGHT: f32 = 16.0;
+const MIN_ORTHOGRAPHIC_HEIGHT: f32 = 2.0;
+const MAX_ORTHOGRAPHIC_HEIGHT: f32 = 60.0;
+const ORTHOGRAPHIC_ZOOM_FACTOR: f32 = 1.1;
+
+// Gamepad controls.
+const STICK_RESPONSE: StickResponse = StickResponse::new(0.2, 2.0);
+const TRIGGER_RESPONSE: StickResponse = StickResponse::new(0.1, 1.0);
+
 // Text colors.
 const TITLE_COLOR: [f32; 3] = [1_f32, 1_f32, 1_f32];
 const TEXT_COLOR: [f32; 3] = [139_f32 / 255_f32, 193_f32 / 255_f32, 248_f32 / 255_f32];
+// Batched text carries its colors in its vertices, so the shader color only passes them through.
+const BATCH_COLOR: [f32; 3] = [1_f32, 1_f32, 1_f32];
+
+// Shadows and outlines that keep the title screen text readable over the skybox.
+const TITLE_EFFECTS: TextEffects = TextEffects {
+    shadow: Some(Shadow { offset_x: 3.0, offset_y: 3.0, color: [0_f32, 0_f32, 0_f32, 0.7], softness: 2.0 }),
+    outline: Some(Outline { thickness: 1.5, color: [0_f32, 0_f32, 0_f32, 1_f32] }),
+};
+const PROMPT_EFFECTS: TextEffects = TextEffects {
+    shadow: Some(Shadow { offset_x: 1.0, offset_y: 1.0, color: [0_f32, 0_f32, 0_f32, 0.8], softness: 0.75 }),
+    outline: None,
+};
+
+// Text effects for signed distance field fonts.
+const TITLE_SDF_STYLE: SdfTextStyle = SdfTextStyle {
+    outline_color: [0_f32, 0_f32, 0_f32, 1_f32],
+    outline_width: 0.02,
+    glow_color: [TEXT_COLOR[0], TEXT_COLOR[1], TEXT_COLOR[2], 0.6],
+    glow_width: 0.08,
+};
+const TEXT_SDF_STYLE: SdfTextStyle = SdfTextStyle {
+    outline_color: [0_f32, 0_f32, 0_f32, 1_f32],
+    outline_width: 0.0,
+    glow_color: [0_f32, 0_f32, 0_f32, 0_f32],
+    glow_width: 0.0,
+};
 
 
 fn arr_to_vec(ptr: *const u8, length: usize) -> Vec<u8> {
@@ -85,8 +182,11 @@ fn load_text_font_atlas(_context: &Game) -> FontAtlas {
     let arr: &'static [u8; 4147] = include_asset!("text_font2048x2048.json");
     let vec = arr_to_vec(&arr[0], 4147);
     let mut reader = io::Cursor::new(vec);
+    let mut atlas = font_atlas::load_reader(&mut reader).unwrap();
+    // Render unsupported characters such as punctuation as blank space.
+    atlas.fallback_glyph = Some(' ');
 
-    font_atlas::load_reader(&mut reader).unwrap()
+    atlas
 }
 
 fn load_title_font_atlas(_context: &Game) -> FontAtlas {
@@ -97,6 +197,15 @@ fn load_title_font_atlas(_context: &Game) -> FontAtlas {
     font_atlas::load_reader(&mut reader).unwrap()
 }
 
+/// Load the camera flythrough played with the P key.
+fn load_camera_path(_context: &Game) -> CameraPath {
+    let arr: &'static [u8; 655] = include_asset!("flythrough.json");
+    let vec = arr_to_vec(&arr[0], 655);
+    let mut reader = io::Cursor::new(vec);
+
+    camera_path::load_reader(&mut reader).unwrap()
+}
+
 /// Create the shaders for rendering text.
 fn create_title_screen_shaders(context: &Game) -> (GLuint, GLint) {
     let mut vert_reader = io::Cursor::new(include_shader!("title_screen.vert.glsl"));
@@ -116,51 +225,81 @@ fn create_title_screen_shaders(context: &Game) -> (GLuint, GLint) {
     (title_screen_sp, title_screen_sp_text_color_loc)
 }
 
-/// Set up the geometry for rendering title screen text.
-fn create_title_screen_geometry(
-    context: &Game, shader: GLuint,
-    font_atlas: &FontAtlas, text: &str,
-    x_pos: f32, y_pos: f32, pixel_scale: f32) -> (GLuint, GLuint, GLuint, usize) {
-    
-    let mut string_vp_vbo = 0;
-    unsafe {
-        gl::GenBuffers(1, &mut string_vp_vbo);
-    }
+/// The outline and glow applied to text drawn from a signed distance field atlas.
+/// The widths are measured in units of the glyph cell size, so the effects scale
+/// along with the text.
+#[derive(Copy, Clone, Debug)]
+struct SdfTextStyle {
+    outline_color: [f32; 4],
+    outline_width: f32,
+    glow_color: [f32; 4],
+    glow_width: f32,
+}
 
-    let mut string_vt_vbo = 0;
-    unsafe {
-        gl::GenBuffers(1, &mut string_vt_vbo);
-    }
+/// The shader program for rendering text from signed distance field font atlases.
+struct SdfTextShader {
+    sp: GLuint,
+    text_color_loc: GLint,
+    outline_color_loc: GLint,
+    outline_width_loc: GLint,
+    glow_color_loc: GLint,
+    glow_width_loc: GLint,
+}
 
-    let mut string_point_count = 0;
-    text_to_vbo(
-        &context.gl, text, &font_atlas,
-        x_pos, y_pos, pixel_scale, 
-        &mut string_vp_vbo, &mut string_vt_vbo, &mut string_point_count
-    );
+/// Create the shaders for rendering text from signed distance field font atlases.
+fn create_title_screen_sdf_shaders(context: &Game) -> SdfTextShader {
+    let mut vert_reader = io::Cursor::new(include_shader!("title_screen.vert.glsl"));
+    let mut frag_reader = io::Cursor::new(include_shader!("title_screen_sdf.frag.glsl"));
+    let sp = glh::create_program_from_reader(
+        &context.gl,
+        &mut vert_reader, "title_screen.vert.glsl",
+        &mut frag_reader, "title_screen_sdf.frag.glsl"
+    ).unwrap();
+    assert!(sp > 0);
 
-    let string_vp_loc = unsafe { gl::GetAttribLocation(shader, glh::gl_str("vp").as_ptr()) };
-    assert!(string_vp_loc > -1);
-    let string_vp_loc = string_vp_loc as u32;
+    let uniform_location = |name: &str| {
+        let location = unsafe { gl::GetUniformLocation(sp, glh::gl_str(name).as_ptr()) };
+        assert!(location > -1);
+        location
+    };
+
+    SdfTextShader {
+        sp: sp,
+        text_color_loc: uniform_location("text_color"),
+        outline_color_loc: uniform_location("outline_color"),
+        outline_width_loc: uniform_location("outline_width"),
+        glow_color_loc: uniform_location("glow_color"),
+        glow_width_loc: uniform_location("glow_width"),
+    }
+}
 
-    let string_vt_loc = unsafe { gl::GetAttribLocation(shader, glh::gl_str("vt").as_ptr()) };
-    assert!(string_vt_loc > -1);
-    let string_vt_loc = string_vt_loc as u32;
+/// Select the shader program for drawing text from a font atlas. Signed distance
+/// field atlases need the distance field shader; bitmap atlases use the plain one.
+fn text_shader_program(atlas: &FontAtlas, title_screen_sp: GLuint, sdf_shader: &SdfTextShader) -> GLuint {
+    match atlas.distance_field {
+        Some(_) => sdf_shader.sp,
+        None => title_screen_sp,
+    }
+}
 
-    let mut string_vao = 0;
+/// Bind the signed distance field text shader and set up its uniforms for drawing a string.
+fn use_sdf_text_shader(shader: &SdfTextShader, field: &DistanceField, color: [f32; 3], style: &SdfTextStyle) {
+    // The shader measures widths in distance field units, where the field
+    // changes by 0.5 over the spread of the atlas.
+    let outline_width = style.outline_width / (2.0 * field.spread);
+    let glow_width = style.glow_width / (2.0 * field.spread);
+    let outline_color = style.outline_color;
+    let glow_color = style.glow_color;
     unsafe {
-        gl::GenVertexArrays(1, &mut string_vao);
-        gl::BindVertexArray(string_vao);
-        gl::BindBuffer(gl::ARRAY_BUFFER, string_vp_vbo);
-        gl::VertexAttribPointer(string_vp_loc, 2, gl::FLOAT, gl::FALSE, 0, ptr::null());
-        gl::EnableVertexAttribArray(string_vp_loc);
-        gl::BindBuffer(gl::ARRAY_BUFFER, string_vt_vbo);
-        gl::VertexAttribPointer(string_vt_loc, 2, gl::FLOAT, gl::FALSE, 0, ptr::null());
-        gl::EnableVertexAttribArray(string_vt_loc);
+        gl::UseProgram(shader.sp);
+        gl::Uniform4f(shader.text_color_loc, color[0], color[1], color[2], 1.0);
+        gl::Uniform4f(
+            shader.outline_color_loc, outline_color[0], outline_color[1], outline_color[2], outline_color[3]
+        );
+        gl::Uniform1f(shader.outline_width_loc, outline_width);
+        gl::Uniform4f(shader.glow_color_loc, glow_color[0], glow_color[1], glow_color[2], glow_color[3]);
+        gl::Uniform1f(shader.glow_width_loc, glow_width);
     }
-    assert!(string_vao > 0);
-
-    (string_vp_vbo, string_vt_vbo, string_vao, string_point_count)
 }
 
 fn create_title_screen_texture(_context: &Game) -> GLuint {
@@ -183,73 +322,6 @@ fn create_text_texture(_context: &Game) -> GLuint {
     tex
 }
 
-/// Print a string to the GLFW screen with the given font.
-fn text_to_vbo(
-    context: &glh::GLState, st: &str, atlas: &FontAtlas,
-    start_x: f32, start_y: f32, scale_px: f32,
-    points_vbo: &mut GLuint, texcoords_vbo: &mut GLuint, point_count: &mut usize) {
-
-    let mut points_temp = vec![0.0; 12 * st.len()];
-    let mut texcoords_temp = vec![0.0; 12 * st.len()];
-    let mut at_x = start_x;
-    let at_y = start_y;
-
-    for (i, ch_i) in st.chars().enumerate() {
-        let address = atlas.glyph_coords[&ch_i];
-        
-        let s = (address.column as f32) * (1.0 / (atlas.columns as f32));
-        let t = ((address.row + 1) as f32) * (1.0 / (atlas.rows as f32));
-
-        let x_pos = at_x;
-        let y_pos = at_y - (scale_px / (context.height as f32)) * atlas.glyph_y_offsets[&ch_i];
-
-        at_x +=  atlas.glyph_widths[&ch_i] * (scale_px / (context.width as f32));
-
-        points_temp[12 * i]     = x_pos;
-        points_temp[12 * i + 1] = y_pos;
-        points_temp[12 * i + 2] = x_pos;
-        points_temp[12 * i + 3] = y_pos - scale_px / (context.height as f32);
-        points_temp[12 * i + 4] = x_pos + scale_px / (context.width as f32);
-        points_temp[12 * i + 5] = y_pos - scale_px / (context.height as f32);
-
-        points_temp[12 * i + 6]  = x_pos + scale_px / (context.width as f32);
-        points_temp[12 * i + 7]  = y_pos - scale_px / (context.height as f32);
-        points_temp[12 * i + 8]  = x_pos + scale_px / (context.width as f32);
-        points_temp[12 * i + 9]  = y_pos;
-        points_temp[12 * i + 10] = x_pos;
-        points_temp[12 * i + 11] = y_pos;
-
-        texcoords_temp[12 * i]     = s;
-        texcoords_temp[12 * i + 1] = 1.0 - t + 1.0 / (atlas.rows as f32);
-        texcoords_temp[12 * i + 2] = s;
-        texcoords_temp[12 * i + 3] = 1.0 - t;
-        texcoords_temp[12 * i + 4] = s + 1.0 / (atlas.columns as f32);
-        texcoords_temp[12 * i + 5] = 1.0 - t;
-
-        texcoords_temp[12 * i + 6]  = s + 1.0 / (atlas.columns as f32);
-        texcoords_temp[12 * i + 7]  = 1.0 - t;
-        texcoords_temp[12 * i + 8]  = s + 1.0 / (atlas.columns as f32);
-        texcoords_temp[12 * i + 9]  = 1.0 - t + 1.0 / (atlas.rows as f32);
-        texcoords_temp[12 * i + 10] = s;
-        texcoords_temp[12 * i + 11] = 1.0 - t + 1.0 / (atlas.rows as f32);
-    }
-
-    unsafe {
-        gl::BindBuffer(gl::ARRAY_BUFFER, *points_vbo);
-        gl::BufferData(
-            gl::ARRAY_BUFFER, (12 * st.len() * mem::size_of::<GLfloat>()) as GLsizeiptr,
-            points_temp.as_ptr() as *const GLvoid, gl::DYNAMIC_DRAW
-        );
-        gl::BindBuffer(gl::ARRAY_BUFFER, *texcoords_vbo);
-        gl::BufferData(
-            gl::ARRAY_BUFFER, (12 * st.len() * mem::size_of::<GLfloat>()) as GLsizeiptr, 
-            texcoords_temp.as_ptr() as *const GLvoid, gl::DYNAMIC_DRAW
-        );
-    }
-
-    *point_count = 6 * st.len();
-}
-
 /// Load the vertex buffer object for the skybox.
 fn create_cube_map_geometry(_context: &Game, shader: GLuint) -> GLuint {
     let cube_map = include_code!("cube_map.obj.in");
@@ -407,9 +479,10 @@ fn create_ground_plane_shaders(context: &Game) -> (GLuint, GLint, GLint) {
     (gp_sp, gp_view_mat_loc, gp_proj_mat_loc)
 }
 
-/// Create the ground plane geometry.
-fn create_ground_plane_geometry(_context: &Game, shader: GLuint) -> (GLuint, GLuint) {
+/// Create the ground plane geometry, and the box bounding it for culling.
+fn create_ground_plane_geometry(_context: &Game, shader: GLuint) -> (GLuint, GLuint, Aabb) {
     let mesh = include_code!("ground_plane.obj.in");
+    let bounds = Aabb::from_points(&mesh.points);
 
     let mut gp_vp_vbo = 0;
     unsafe {
@@ -437,7 +510,7 @@ fn create_ground_plane_geometry(_context: &Game, shader: GLuint) -> (GLuint, GLu
     }
     assert!(gp_vp_vao > 0);
 
-    (gp_vp_vbo, gp_vp_vao)
+    (gp_vp_vbo, gp_vp_vao, bounds)
 }
 
 /// Create the ground plane texture.
@@ -477,6 +550,89 @@ fn reset_camera_to_default(context: &glh::GLState, camera: &mut Camera) {
     *camera = create_camera(width, height);
 }
 
+/// Load the camera bookmarks saved by earlier runs of the demo.
+fn load_camera_bookmarks() -> CameraBookmarks {
+    match camera_bookmarks::load_file(BOOKMARKS_FILE) {
+        Ok(bookmarks) => bookmarks,
+        // No bookmarks have been saved yet.
+        Err(camera_bookmarks::Error::FileNotFound(_)) => CameraBookmarks::new(),
+        Err(e) => {
+            error!("{}", e);
+            CameraBookmarks::new()
+        }
+    }
+}
+
+fn load_gamepad_layout() -> GamepadLayout {
+    match gamepad::load_file(GAMEPAD_LAYOUT_FILE) {
+        Ok(layout) => layout,
+        // Without a layout file, assume an Xbox controller.
+        Err(gamepad::Error::FileNotFound(_)) => GamepadLayout::native(),
+        Err(e) => {
+            error!("{}", e);
+            GamepadLayout::native()
+        }
+    }
+}
+
+/// The camera bookmark selected by a number key.
+fn camera_bookmark_slot(key: Key) -> Option<usize> {
+    match key {
+        Key::Num1 => Some(1),
+        Key::Num2 => Some(2),
+        Key::Num3 => Some(3),
+        Key::Num4 => Some(4),
+        Key::Num5 => Some(5),
+        Key::Num6 => Some(6),
+        Key::Num7 => Some(7),
+        Key::Num8 => Some(8),
+        Key::Num9 => Some(9),
+        _ => None,
+    }
+}
+
+/// Create the smoothing for the camera's movement and turning. The camera speeds up 
+/// to full speed in about a sixth of a second, and sprints at three times its speed.
+fn create_camera_motion() -> CameraMotion {
+    let acceleration = 6.0;
+    let max_speed = 1.0;
+    let damping = 6.0;
+    let sprint_multiplier = 3.0;
+    let turn_smoothing = 0.08;
+
+    CameraMotion::new(acceleration, max_speed, damping, sprint_multiplier, turn_smoothing)
+}
+
+/// Create the limits on where the camera can go. The camera stays above the ground, 
+/// and within a little way of the edges of the ground plane.
+fn create_camera_constraints(ground_plane_bounds: &Aabb) -> CameraConstraints {
+    let mut constraints = CameraConstraints::new(0.25);
+    constraints.ground_height = ground_plane_bounds.max.z;
+    constraints.min_height = Some(0.5);
+    constraints.world_bounds = Some(Aabb::new(
+        ground_plane_bounds.min + Vector3::new(-5.0, -5.0, 0.0),
+        ground_plane_bounds.max + Vector3::new(5.0, 5.0, 25.0)
+    ));
+
+    constraints
+}
+
+/// The ways to navigate the scene with the camera.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum CameraMode {
+    FreeFly,
+    Orbit,
+    Walk,
+}
+
+fn create_camera_controller(mode: CameraMode, ground_height: f32) -> Box<dyn CameraController> {
+    match mode {
+        CameraMode::FreeFly => Box::new(FreeFlyController::new()),
+        CameraMode::Orbit => Box::new(OrbitController::new(8.0, 1.0, 40.0)),
+        CameraMode::Walk => Box::new(WalkController::new(ground_height, 1.7)),
+    }
+}
+
 /// Load texture image into the GPU.
 fn load_texture(tex_data: &TexImage2D, wrapping_mode: GLuint) -> Result<GLuint, String> {
     let mut tex = 0;
@@ -516,8 +672,7 @@ fn glfw_framebuffer_size_callback(context: &mut glh::GLState, camera: &mut Camer
     context.height = height;
 
     let aspect = context.width as f32 / context.height as f32;
-    camera.aspect = aspect;
-    camera.proj_mat = Matrix4::from_perspective_fov(Degrees(camera.fov), aspect, camera.near, camera.far);
+    camera.set_aspect(aspect);
     unsafe {
         gl::Viewport(0, 0, context.width as i32, context.height as i32);
     }
@@ -555,6 +710,16 @@ fn start() -> Game {
     Game::new(gl_context)
 }
 
+/// Place the title and the prompt one above the other, separated by a gap, with
+/// the pair centered on the screen.
+fn title_screen_placements(title_bounds: &TextBounds, prompt_bounds: &TextBounds) -> (Placement, Placement) {
+    let total_height = title_bounds.height + TITLE_PROMPT_GAP_PX + prompt_bounds.height;
+    let title_offset_y = 0.5 * (title_bounds.height - total_height);
+    let prompt_offset_y = 0.5 * (total_height - prompt_bounds.height);
+
+    (Placement::new(Anchor::Center, 0.0, title_offset_y), Placement::new(Anchor::Center, 0.0, prompt_offset_y))
+}
+
 #[allow(unused_variables)]
 fn main() {
     let mut context = start();
@@ -569,7 +734,8 @@ fn main() {
     
     let (
         ground_plane_points_vbo,
-        ground_plane_points_vao) = create_ground_plane_geometry(&context, gp_sp);
+        ground_plane_points_vao,
+        ground_plane_bounds) = create_ground_plane_geometry(&context, gp_sp);
 
     // Texture for the ground plane.
     let gp_tex = create_ground_plane_texture(&context);
@@ -578,28 +744,29 @@ fn main() {
     let (
         title_screen_sp,
         title_screen_sp_color_loc) = create_title_screen_shaders(&context);
+    let title_screen_sdf = create_title_screen_sdf_shaders(&context);
 
     // Screen text.
-    let (
-        string_vp_vbo,
-        string_vt_vbo,
-        string_vao,
-        string_points
-    ) = create_title_screen_geometry(
-        &context, title_screen_sp, &text_font_atlas, "Press ENTER to continue", -0.65, -0.40, 40.0
+    let mut string_params = LayoutParams::new(20.0);
+    string_params.markup = true;
+    // All the strings set in the text font are drawn together in one batch.
+    let mut text_batch = TextBatch::new(
+        text_shader_program(&text_font_atlas, title_screen_sp, &title_screen_sdf), 64
     );
 
     // Font sheet for the title screen text.
     let text_screen_tex = create_text_texture(&context);
 
     // Title text.
-    let (
-        title_vp_vbo,
-        title_vt_vbo,
-        title_vao,
-        title_points
-    ) = create_title_screen_geometry(
-        &context, title_screen_sp, &title_font_atlas, "LAMBDAXYMOX", -0.80, 0.4, 256.0
+    let title_params = LayoutParams::new(128.0);
+    let mut title_text = TextBuffer::new(
+        text_shader_program(&title_font_atlas, title_screen_sp, &title_screen_sdf), TITLE_TEXT.len()
+    );
+
+    // Stack the title above the prompt around the center of the screen.
+    let (title_placement, prompt_placement) = title_screen_placements(
+        &text::measure(&title_font_atlas, TITLE_TEXT, &title_params),
+        &text::measure(&text_font_atlas, PROMPT_TEXT, &string_params)
     );
 
     // Font sheet for the title text on the title screen.
@@ -619,6 +786,19 @@ fn main() {
     assert!(cube_map_texture > 0);
 
     let mut camera = create_camera(context.gl.width, context.gl.height);
+    let mut camera_mode = CameraMode::FreeFly;
+    let mut camera_controller = create_camera_controller(camera_mode, ground_plane_bounds.max.z);
+    let mut camera_motion = create_camera_motion();
+    let camera_constraints = create_camera_constraints(&ground_plane_bounds);
+    let mut camera_constraints_enabled = true;
+    let mut cursor_captured = false;
+    let mut last_cursor_pos = (0.0, 0.0);
+    let mut camera_path_player = CameraPathPlayer::new(load_camera_path(&context));
+    let mut camera_bookmarks = load_camera_bookmarks();
+    let mut last_culling_stats = CullingStats::new();
+    let mut gamepads = Gamepads::new(
+        &context.gl.glfw, load_gamepad_layout(), STICK_RESPONSE, TRIGGER_RESPONSE
+    );
 
     unsafe {
         gl::UseProgram(gp_sp);
@@ -646,14 +826,33 @@ fn main() {
         gl::Viewport(0, 0, context.gl.width as i32, context.gl.height as i32);
     }
 
+    // The prompt is laid out once, and only its animations change from frame to frame.
+    let mut prompt_layout = text::layout(&text_font_atlas, PROMPT_TEXT, &string_params);
+    let prompt_reveal_seconds = text_animation::reveal_seconds(&prompt_layout, PROMPT_CHARS_PER_SECOND);
+
+    // The title screen animations are timed from the first frame.
+    let title_screen_start_seconds = context.gl.glfw.get_time();
+
     /* -------------------------- RENDERING LOOP --------------------------- */
     while !context.gl.window.should_close() {
         let elapsed_seconds = glh::update_timers(&mut context.gl);
         glh::update_fps_counter(&mut context.gl);
 
+        let mut proj_changed = false;
         let (width, height) = context.gl.window.get_framebuffer_size();
-        if (width != context.gl.width as i32) && (height != context.gl.height as i32) {
+        if (width != context.gl.width as i32) || (height != context.gl.height as i32) {
             glfw_framebuffer_size_callback(&mut context.gl, &mut camera, width as u32, height as u32);
+            proj_changed = true;
+        }
+
+        // Skip drawing the scene objects outside the view. The sky box surrounds
+        // the camera, so it is always drawn.
+        let frustum = camera.frustum();
+        let mut culling_stats = CullingStats::new();
+        let ground_plane_visible = culling_stats.record(frustum.intersects_aabb(&ground_plane_bounds));
+        if culling_stats != last_culling_stats {
+            info!("Culled {} of {} scene objects.", culling_stats.culled, culling_stats.tested);
+            last_culling_stats = culling_stats;
         }
 
         unsafe {
@@ -671,172 +870,353 @@ fn main() {
             gl::DepthMask(gl::TRUE);
 
             // Draw the ground plane.
-            gl::UseProgram(gp_sp);
-            gl::ActiveTexture(gl::TEXTURE0);
-            gl::BindTexture(gl::TEXTURE_2D, gp_tex);
-            gl::BindVertexArray(ground_plane_points_vao);
-            gl::DrawArrays(gl::TRIANGLES, 0, 6);
+            if ground_plane_visible {
+                gl::UseProgram(gp_sp);
+                gl::ActiveTexture(gl::TEXTURE0);
+                gl::BindTexture(gl::TEXTURE_2D, gp_tex);
+                gl::BindVertexArray(ground_plane_points_vao);
+                gl::DrawArrays(gl::TRIANGLES, 0, 6);
+            }
             
             // Draw the title screen. Disable depth testing and enable 
             // alpha blending to do so.
             gl::Disable(gl::DEPTH_TEST);
-            gl::UseProgram(title_screen_sp);
             gl::ActiveTexture(gl::TEXTURE0);
             
             gl::Enable(gl::BLEND);
             gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
             gl::BindTexture(gl::TEXTURE_2D, title_screen_tex);
-            gl::BindVertexArray(title_vao);
-            gl::Uniform4f(title_screen_sp_color_loc, TITLE_COLOR[0], TITLE_COLOR[1], TITLE_COLOR[2], 1.0);
-            gl::DrawArrays(gl::TRIANGLES, 0, title_points as i32);
-            gl::Disable(gl::BLEND);
+            title_text.update(
+                &context.gl, &title_font_atlas, TITLE_TEXT, &title_params, &title_placement, &TITLE_EFFECTS
+            );
+            match title_font_atlas.distance_field {
+                Some(ref field) => use_sdf_text_shader(&title_screen_sdf, field, TITLE_COLOR, &TITLE_SDF_STYLE),
+                None => {
+                    gl::UseProgram(title_screen_sp);
+                    gl::Uniform4f(title_screen_sp_color_loc, TITLE_COLOR[0], TITLE_COLOR[1], TITLE_COLOR[2], 1.0);
+                }
+            }
+            title_text.draw();
 
             gl::BindTexture(gl::TEXTURE_2D, text_screen_tex);
-            gl::BindVertexArray(string_vao);
-            gl::Uniform4f(title_screen_sp_color_loc, TEXT_COLOR[0], TEXT_COLOR[2], TEXT_COLOR[2], 1.0);
-            gl::DrawArrays(gl::TRIANGLES, 0, string_points as i32);
+            match text_font_atlas.distance_field {
+                Some(ref field) => use_sdf_text_shader(&title_screen_sdf, field, BATCH_COLOR, &TEXT_SDF_STYLE),
+                None => {
+                    gl::UseProgram(title_screen_sp);
+                    gl::Uniform4f(title_screen_sp_color_loc, BATCH_COLOR[0], BATCH_COLOR[1], BATCH_COLOR[2], 1.0);
+                }
+            }
+            let title_screen_seconds = (context.gl.running_time_seconds - title_screen_start_seconds) as f32;
+            text_animation::reset(&mut prompt_layout);
+            PROMPT_TYPEWRITER.apply(&mut prompt_layout, title_screen_seconds);
+            PROMPT_BLINK.apply(&mut prompt_layout, title_screen_seconds - prompt_reveal_seconds);
+            text_batch.push_layout(
+                &context.gl, &text_font_atlas, &prompt_layout, &prompt_placement, &PROMPT_EFFECTS,
+                [TEXT_COLOR[0], TEXT_COLOR[1], TEXT_COLOR[2], 1.0]
+            );
+            text_batch.flush();
+            gl::Disable(gl::BLEND);
             gl::Enable(gl::DEPTH_TEST);
         }
 
         context.gl.glfw.poll_events();
+        gamepads.update();
+
+        // Gather the mouse wheel scrolling and the toggle keys since the last frame.
+        let mut scroll_y = 0.0;
+        let mut toggle_camera_path = false;
+        let mut toggle_projection = false;
+        let mut toggle_constraints = false;
+        let mut store_bookmark = None;
+        let mut recall_bookmark = None;
+        let mut clicked = false;
+        for (_, event) in glfw::flush_messages(&context.gl.events) {
+            match event {
+                WindowEvent::Scroll(_, y) => scroll_y += y,
+                WindowEvent::Key(Key::P, _, Action::Press, _) => toggle_camera_path = true,
+                WindowEvent::Key(Key::O, _, Action::Press, _) => toggle_projection = true,
+                WindowEvent::Key(Key::B, _, Action::Press, _) => toggle_constraints = true,
+                WindowEvent::MouseButton(MouseButton::Button1, Action::Press, _) => clicked = true,
+                WindowEvent::Key(key, _, Action::Press, modifiers) => {
+                    if let Some(slot) = camera_bookmark_slot(key) {
+                        if modifiers.contains(Modifiers::Control) {
+                            store_bookmark = Some(slot);
+                        } else {
+                            recall_bookmark = Some(slot);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
 
         /* ------------------------- UPDATE GAME STATE ------------------------ */
-        // Camera control keys.
+        // Camera mode keys.
         let mut cam_moved = false;
-        let mut move_to = Vector3::new(0.0, 0.0, 0.0);
-        let mut cam_yaw = 0.0;
-        let mut cam_pitch = 0.0;
-        let mut cam_roll = 0.0;
+        let selected_mode = if context.gl.window.get_key(Key::F1) == Action::Press {
+            Some(CameraMode::FreeFly)
+        } else if context.gl.window.get_key(Key::F2) == Action::Press {
+            Some(CameraMode::Orbit)
+        } else if context.gl.window.get_key(Key::F3) == Action::Press {
+            Some(CameraMode::Walk)
+        } else {
+            None
+        };
+        match selected_mode {
+            Some(mode) if mode != camera_mode => {
+                camera_mode = mode;
+                camera_controller = create_camera_controller(camera_mode, ground_plane_bounds.max.z);
+                camera_controller.attach(&mut camera);
+                camera_motion.reset();
+                info!("Switched to the {} camera.", camera_controller.name());
+                cam_moved = true;
+            }
+            _ => {}
+        }
+
+        // Camera control keys.
+        let mut camera_input = CameraInput::new();
         match context.gl.window.get_key(Key::A) {
             Action::Press | Action::Repeat => {
-                move_to.x -= camera.cam_speed * (elapsed_seconds as GLfloat);
-                cam_moved = true;
+                camera_input.move_x -= 1.0;
             }
             _ => {}
         }
         match context.gl.window.get_key(Key::D) {
             Action::Press | Action::Repeat => {
-                move_to.x += camera.cam_speed * (elapsed_seconds as GLfloat);
-                cam_moved = true;
+                camera_input.move_x += 1.0;
             }
             _ => {}
         }
         match context.gl.window.get_key(Key::Q) {
             Action::Press | Action::Repeat => {
-                move_to.y += camera.cam_speed * (elapsed_seconds as GLfloat);
-                cam_moved = true;
+                camera_input.move_y += 1.0;
             }
             _ => {}
         }
         match context.gl.window.get_key(Key::E) {
             Action::Press | Action::Repeat => {
-                move_to.y -= camera.cam_speed * (elapsed_seconds as GLfloat);
-                cam_moved = true;
+                camera_input.move_y -= 1.0;
             }
             _ => {}
         }
         match context.gl.window.get_key(Key::W) {
             Action::Press | Action::Repeat => {
-                move_to.z -= camera.cam_speed * (elapsed_seconds as GLfloat);
-                cam_moved = true;
+                camera_input.move_z -= 1.0;
             }
             _ => {}
         }
         match context.gl.window.get_key(Key::S) {
             Action::Press | Action::Repeat => {
-                move_to.z += camera.cam_speed * (elapsed_seconds as GLfloat);
-                cam_moved = true;
+                camera_input.move_z += 1.0;
             }
             _ => {}
         }
         match context.gl.window.get_key(Key::Left) {
             Action::Press | Action::Repeat => {
-                cam_yaw += camera.cam_yaw_speed * (elapsed_seconds as GLfloat);
-                cam_moved = true;
-                let yaw_axis = Unit::from_value(camera.up.contract());
-                let q_yaw = Quaternion::from_axis_angle(&yaw_axis, Degrees(cam_yaw));
-                camera.axis = q_yaw * &camera.axis;
+                camera_input.yaw += 1.0;
             }
             _ => {}
         }
         match context.gl.window.get_key(Key::Right) {
             Action::Press | Action::Repeat => {
-                cam_yaw -= camera.cam_yaw_speed * (elapsed_seconds as GLfloat);
-                cam_moved = true;
-                let yaw_axis = Unit::from_value(camera.up.contract());
-                let q_yaw = Quaternion::from_axis_angle(&yaw_axis, Degrees(cam_yaw));
-                camera.axis = q_yaw * &camera.axis;
+                camera_input.yaw -= 1.0;
             }
             _ => {}
         }
         match context.gl.window.get_key(Key::Up) {
             Action::Press | Action::Repeat => {
-                cam_pitch += camera.cam_yaw_speed * (elapsed_seconds as GLfloat);
-                cam_moved = true;
-                let pitch_axis = Unit::from_value(camera.rgt.contract());
-                let q_pitch = Quaternion::from_axis_angle(&pitch_axis, Degrees(cam_pitch));
-                camera.axis = q_pitch * &camera.axis;
+                camera_input.pitch += 1.0;
             }
             _ => {}
         }
         match context.gl.window.get_key(Key::Down) {
             Action::Press | Action::Repeat => {
-                cam_pitch -= camera.cam_yaw_speed * (elapsed_seconds as GLfloat);
-                cam_moved = true;
-                let pitch_axis = Unit::from_value(camera.rgt.contract());
-                let q_pitch = Quaternion::from_axis_angle(&pitch_axis, Degrees(cam_pitch));
-                camera.axis = q_pitch * &camera.axis;
+                camera_input.pitch -= 1.0;
             }
             _ => {}
         }
         match context.gl.window.get_key(Key::Z) {
             Action::Press | Action::Repeat => {
-                cam_roll -= camera.cam_yaw_speed * (elapsed_seconds as GLfloat);
-                cam_moved = true;
-                let roll_axis = Unit::from_value(camera.fwd.contract());
-                let q_roll = Quaternion::from_axis_angle(&roll_axis, Degrees(cam_roll));
-                camera.axis = q_roll * &camera.axis;
+                camera_input.roll -= 1.0;
             }
             _ => {}
         }
         match context.gl.window.get_key(Key::C) {
             Action::Press | Action::Repeat => {
-                cam_roll += camera.cam_yaw_speed * (elapsed_seconds as GLfloat);
-                cam_moved = true;
-                let roll_axis = Unit::from_value(camera.fwd.contract());
-                let q_roll = Quaternion::from_axis_angle(&roll_axis, Degrees(cam_roll));
-                camera.axis = q_roll * &camera.axis;
+                camera_input.roll += 1.0;
+            }
+            _ => {}
+        }
+        gamepads.camera_input(&mut camera_input);
+
+        // Mouse look. The cursor is hidden and held in the window while it is captured.
+        match context.gl.window.get_key(Key::M) {
+            Action::Press | Action::Repeat => {
+                if !cursor_captured {
+                    context.gl.window.set_cursor_mode(CursorMode::Disabled);
+                    last_cursor_pos = context.gl.window.get_cursor_pos();
+                    cursor_captured = true;
+                }
             }
             _ => {}
         }
+        match context.gl.window.get_key(Key::Tab) {
+            Action::Press | Action::Repeat => {
+                if cursor_captured {
+                    context.gl.window.set_cursor_mode(CursorMode::Normal);
+                    cursor_captured = false;
+                }
+            }
+            _ => {}
+        }
+        if cursor_captured {
+            let (cursor_x, cursor_y) = context.gl.window.get_cursor_pos();
+            let dx = cursor_x - last_cursor_pos.0;
+            let dy = cursor_y - last_cursor_pos.1;
+            MOUSE_LOOK.look(&camera, dx, dy, &mut camera_input);
+            last_cursor_pos = (cursor_x, cursor_y);
+        }
+
+        // Speed up and slow down smoothly. Either shift key sprints.
+        let sprinting = match (context.gl.window.get_key(Key::LeftShift), context.gl.window.get_key(Key::RightShift)) {
+            (Action::Release, Action::Release) => false,
+            _ => true,
+        };
+        let camera_input = camera_motion.smooth(&camera_input, sprinting, elapsed_seconds as GLfloat);
+
+        if toggle_constraints {
+            camera_constraints_enabled = !camera_constraints_enabled;
+            info!("Camera collisions {}.", if camera_constraints_enabled { "enabled" } else { "disabled" });
+        }
+
+        // Play or pause the camera flythrough. The controller takes over the camera
+        // from wherever the flythrough leaves it.
+        if toggle_camera_path {
+            camera_path_player.toggle();
+            if !camera_path_player.playing {
+                camera_controller.attach(&mut camera);
+            }
+        }
+        if camera_path_player.playing {
+            camera_motion.reset();
+            if camera_path_player.update(&mut camera, elapsed_seconds as GLfloat) {
+                cam_moved = true;
+            }
+            if !camera_path_player.playing {
+                camera_controller.attach(&mut camera);
+            }
+        } else {
+            let previous_pos = camera.cam_pos;
+            if camera_controller.update(&mut camera, &camera_input, elapsed_seconds as GLfloat) {
+                cam_moved = true;
+            }
+            // Keep the camera out of the ground and inside the world. The flythrough
+            // is scripted, so it is left alone.
+            if camera_constraints_enabled && camera_constraints.constrain(&mut camera, &previous_pos) {
+                cam_moved = true;
+            }
+        }
         match context.gl.window.get_key(Key::Backspace) {
             Action::Press | Action::Repeat => {
                 reset_camera_to_default(&context.gl, &mut camera);
+                camera_controller.attach(&mut camera);
+                camera_motion.reset();
                 cam_moved = true;
+                proj_changed = true;
             }
             _ => {}
         }
-        match context.gl.window.get_key(Key::Enter) {
-            Action::Press | Action::Repeat => {
-                println!("ENTER key pressed.");
+        let enter_pressed = match context.gl.window.get_key(Key::Enter) {
+            Action::Press | Action::Repeat => true,
+            _ => false,
+        };
+        if enter_pressed || gamepads.is_start_pressed() {
+            println!("ENTER key pressed.");
+        }
+
+        // Pick the point on the ground plane under the cursor. While the cursor is
+        // captured for mouse-look, pick the point in the middle of the screen.
+        if clicked {
+            let (window_width, window_height) = context.gl.window.get_size();
+            let (window_width, window_height) = (window_width as f64, window_height as f64);
+            let (cursor_x, cursor_y) = if cursor_captured {
+                (0.5 * window_width, 0.5 * window_height)
+            } else {
+                context.gl.window.get_cursor_pos()
+            };
+            let ray = camera.screen_ray(cursor_x, cursor_y, window_width, window_height);
+            let ground = Plane::new(Vector3::new(0.0, 0.0, 1.0), 0.0);
+            let hit = ray.intersect_plane(&ground)
+                .map(|distance| ray.at(distance))
+                .filter(|point| {
+                    point.x >= ground_plane_bounds.min.x && point.x <= ground_plane_bounds.max.x &&
+                    point.y >= ground_plane_bounds.min.y && point.y <= ground_plane_bounds.max.y
+                });
+            match hit {
+                Some(point) => info!("Clicked the ground plane at ({:.2}, {:.2}, {:.2}).", point.x, point.y, point.z),
+                None => info!("Clicked, but missed the ground plane."),
+            }
+        }
+
+        // Camera bookmarks. Control and a number key saves the view, and the number
+        // key alone jumps back to it.
+        if let Some(slot) = store_bookmark {
+            camera_bookmarks.store(slot, &camera);
+            info!("Saved camera bookmark {}.", slot);
+            if let Err(e) = camera_bookmarks::write_file(&camera_bookmarks, BOOKMARKS_FILE) {
+                error!("{}", e);
+            }
+        }
+        if let Some(slot) = recall_bookmark {
+            if camera_bookmarks.recall(slot, &mut camera) {
+                camera_path_player.pause();
+                camera_controller.attach(&mut camera);
+                camera_motion.reset();
+                cam_moved = true;
+                proj_changed = true;
+            }
+        }
+
+        // Switch between perspective and orthographic projection.
+        if toggle_projection {
+            let projection = match camera.projection {
+                Projection::Perspective => Projection::Orthographic { height: ORTHOGRAPHIC_HEIGHT },
+                Projection::Orthographic { .. } => Projection::Perspective,
+            };
+            camera.set_projection(projection);
+            info!("Switched to {:?} projection.", camera.projection);
+            proj_changed = true;
+        }
+
+        // Zoom with the mouse wheel.
+        if scroll_y != 0.0 {
+            match camera.projection {
+                Projection::Perspective => {
+                    let fov = camera.fov - FOV_ZOOM_STEP * (scroll_y as f32);
+                    camera.set_fov(f32::max(MIN_FOV, f32::min(fov, MAX_FOV)));
+                }
+                Projection::Orthographic { height } => {
+                    let height = height * f32::powf(ORTHOGRAPHIC_ZOOM_FACTOR, -scroll_y as f32);
+                    let height = f32::max(MIN_ORTHOGRAPHIC_HEIGHT, f32::min(height, MAX_ORTHOGRAPHIC_HEIGHT));
+                    camera.set_projection(Projection::Orthographic { height: height });
+                }
+            }
+            proj_changed = true;
+        }
+
+        // Update projection matrix.
+        if proj_changed {
+            unsafe {
+                gl::UseProgram(gp_sp);
+                gl::UniformMatrix4fv(gp_proj_mat_loc, 1, gl::FALSE, camera.proj_mat.as_ptr());
+                gl::UseProgram(cube_sp);
+                gl::UniformMatrix4fv(cube_proj_mat_location, 1, gl::FALSE, camera.proj_mat.as_ptr());
             }
-            _ => {}
         }
 
         // Update view matrix.
         if cam_moved {
-            // Recalculate local axes so we can move fwd in the direction the camera is pointing.
-            camera.rot_mat_inv = Matrix4::from(camera.axis);
-            camera.fwd = camera.rot_mat_inv * Vector4::new(0.0, 0.0, -1.0, 0.0);
-            camera.rgt = camera.rot_mat_inv * Vector4::new(1.0, 0.0,  0.0, 0.0);
-            camera.up  = camera.rot_mat_inv * Vector4::new(0.0, 1.0,  0.0, 0.0);
-
-            camera.cam_pos += camera.fwd.contract() * -move_to.z;
-            camera.cam_pos += camera.up.contract()  *  move_to.y;
-            camera.cam_pos += camera.rgt.contract() *  move_to.x;
-            camera.trans_mat_inv = Matrix4::from_affine_translation(&camera.cam_pos);
-
-            camera.view_mat = camera.rot_mat_inv.inverse().unwrap() * camera.trans_mat_inv.inverse().unwrap();
             unsafe {
                 gl::UseProgram(gp_sp);
                 gl::UniformMatrix4fv(gp_view_mat_loc, 1, gl::FALSE, camera.view_mat.as_ptr());
@@ -0,0 +1,187 @@
+#![allow(dead_code)]
+use cglinalg::Vector3;
+use crate::frustum::{Aabb, Plane};
+
+
+/// A half line in world space, starting at `origin` and running along `direction`,
+/// which has unit length. Distances along the ray are in world units.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Ray {
+    pub origin: Vector3<f32>,
+    pub direction: Vector3<f32>,
+}
+
+impl Ray {
+    pub fn new(origin: Vector3<f32>, direction: Vector3<f32>) -> Ray {
+        Ray {
+            origin: origin,
+            direction: direction.normalize(),
+        }
+    }
+
+    /// The point `distance` units along the ray.
+    #[inline]
+    pub fn at(&self, distance: f32) -> Vector3<f32> {
+        self.origin + self.direction * distance
+    }
+
+    /// The distance along the ray to where it crosses `plane`, from either side.
+    /// Returns `None` when the ray runs parallel to the plane or points away from it.
+    pub fn intersect_plane(&self, plane: &Plane) -> Option<f32> {
+        let denominator = plane.normal.dot(&self.direction);
+        if f32::abs(denominator) < 1e-6 {
+            return None;
+        }
+        let distance = -plane.signed_distance(&self.origin) / denominator;
+        if distance < 0.0 {
+            return None;
+        }
+
+        Some(distance)
+    }
+
+    /// The distance along the ray to where it enters `aabb`, or zero when the ray
+    /// starts inside the box. Returns `None` when the ray misses the box.
+    pub fn intersect_aabb(&self, aabb: &Aabb) -> Option<f32> {
+        let origin = [self.origin.x, self.origin.y, self.origin.z];
+        let direction = [self.direction.x, self.direction.y, self.direction.z];
+        let min = [aabb.min.x, aabb.min.y, aabb.min.z];
+        let max = [aabb.max.x, aabb.max.y, aabb.max.z];
+
+        // Clip the ray against the slab between each pair of opposite faces.
+        let mut t_enter = 0.0;
+        let mut t_exit = f32::INFINITY;
+        for axis in 0..3 {
+            if f32::abs(direction[axis]) < 1e-6 {
+                if origin[axis] < min[axis] || origin[axis] > max[axis] {
+                    return None;
+                }
+            } else {
+                let t_min = (min[axis] - origin[axis]) / direction[axis];
+                let t_max = (max[axis] - origin[axis]) / direction[axis];
+                t_enter = f32::max(t_enter, f32::min(t_min, t_max));
+                t_exit = f32::min(t_exit, f32::max(t_min, t_max));
+                if t_enter > t_exit {
+                    return None;
+                }
+            }
+        }
+
+        Some(t_enter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+
+    const EPSILON: f32 = 1e-4;
+
+    fn assert_vector3_eq(result: Vector3<f32>, expected: Vector3<f32>) {
+        assert!(
+            (result - expected).magnitude() < EPSILON,
+            "expected {:?}, got {:?}", expected, result
+        );
+    }
+
+    fn assert_distance_eq(result: Option<f32>, expected: f32) {
+        match result {
+            Some(distance) => assert!(
+                f32::abs(distance - expected) < EPSILON,
+                "expected {}, got {}", expected, distance
+            ),
+            None => panic!("expected a hit at {}, got None", expected),
+        }
+    }
+
+    fn ground() -> Plane {
+        Plane::new(Vector3::new(0.0, 0.0, 1.0), 0.0)
+    }
+
+    fn unit_box() -> Aabb {
+        Aabb::new(Vector3::new(-1.0, -1.0, -1.0), Vector3::new(1.0, 1.0, 1.0))
+    }
+
+    #[test]
+    fn test_ray_direction_has_unit_length() {
+        let ray = Ray::new(Vector3::new(1.0, 2.0, 3.0), Vector3::new(0.0, 3.0, 4.0));
+
+        assert_vector3_eq(ray.direction, Vector3::new(0.0, 0.6, 0.8));
+        assert_vector3_eq(ray.at(5.0), Vector3::new(1.0, 5.0, 7.0));
+    }
+
+    #[test]
+    fn test_ray_hits_plane_from_either_side() {
+        let above = Ray::new(Vector3::new(1.0, 2.0, 4.0), Vector3::new(0.0, 0.0, -1.0));
+        let below = Ray::new(Vector3::new(1.0, 2.0, -4.0), Vector3::new(0.0, 0.0, 1.0));
+        let slanted = Ray::new(Vector3::new(0.0, 0.0, 3.0), Vector3::new(4.0, 0.0, -3.0));
+
+        assert_distance_eq(above.intersect_plane(&ground()), 4.0);
+        assert_distance_eq(below.intersect_plane(&ground()), 4.0);
+        assert_distance_eq(slanted.intersect_plane(&ground()), 5.0);
+        assert_vector3_eq(slanted.at(5.0), Vector3::new(4.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_ray_parallel_to_plane_misses_it() {
+        let ray = Ray::new(Vector3::new(0.0, 0.0, 2.0), Vector3::new(1.0, 1.0, 0.0));
+
+        assert_eq!(ray.intersect_plane(&ground()), None);
+    }
+
+    #[test]
+    fn test_ray_pointing_away_from_plane_misses_it() {
+        let ray = Ray::new(Vector3::new(0.0, 0.0, 2.0), Vector3::new(0.0, 1.0, 1.0));
+
+        assert_eq!(ray.intersect_plane(&ground()), None);
+    }
+
+    #[test]
+    fn test_ray_starting_on_plane_hits_it_immediately() {
+        let ray = Ray::new(Vector3::new(3.0, 1.0, 0.0), Vector3::new(0.0, 0.0, 1.0));
+
+        assert_distance_eq(ray.intersect_plane(&ground()), 0.0);
+    }
+
+    #[test]
+    fn test_ray_hits_nearest_face_of_box() {
+        let ray = Ray::new(Vector3::new(0.0, 0.0, 5.0), Vector3::new(0.0, 0.0, -1.0));
+        let diagonal = Ray::new(Vector3::new(-3.0, -3.0, 0.0), Vector3::new(1.0, 1.0, 0.0));
+
+        assert_distance_eq(ray.intersect_aabb(&unit_box()), 4.0);
+        assert_distance_eq(diagonal.intersect_aabb(&unit_box()), 2.0 * f32::sqrt(2.0));
+    }
+
+    #[test]
+    fn test_ray_starting_inside_box_hits_it_immediately() {
+        let ray = Ray::new(Vector3::new(0.5, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0));
+
+        assert_distance_eq(ray.intersect_aabb(&unit_box()), 0.0);
+    }
+
+    #[test]
+    fn test_ray_pointing_away_from_box_misses_it() {
+        let ray = Ray::new(Vector3::new(0.0, 0.0, 5.0), Vector3::new(0.0, 0.0, 1.0));
+
+        assert_eq!(ray.intersect_aabb(&unit_box()), None);
+    }
+
+    #[test]
+    fn test_ray_passing_beside_box_misses_it() {
+        let ray = Ray::new(Vector3::new(0.0, 3.0, 5.0), Vector3::new(0.0, 0.0, -1.0));
+        let skew = Ray::new(Vector3::new(-3.0, 0.0, 5.0), Vector3::new(1.0, 0.0, -0.2));
+
+        assert_eq!(ray.intersect_aabb(&unit_box()), None);
+        assert_eq!(skew.intersect_aabb(&unit_box()), None);
+    }
+
+    #[test]
+    fn test_ray_parallel_to_box_faces_hits_only_within_their_slab() {
+        let within = Ray::new(Vector3::new(-5.0, 0.5, 0.0), Vector3::new(1.0, 0.0, 0.0));
+        let outside = Ray::new(Vector3::new(-5.0, 1.5, 0.0), Vector3::new(1.0, 0.0, 0.0));
+
+        assert_distance_eq(within.intersect_aabb(&unit_box()), 4.0);
+        assert_eq!(outside.intersect_aabb(&unit_box()), None);
+    }
+}
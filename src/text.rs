@@ -0,0 +1,1079 @@
+#![allow(dead_code)]
+use crate::font_atlas::FontAtlas;
+use log::warn;
+use unicode_segmentation::UnicodeSegmentation;
+
+
+/// The horizontal alignment of each line of text inside its layout box.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Alignment {
+    Left,
+    Center,
+    Right,
+}
+
+/// The parameters that control how a string is laid out.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LayoutParams {
+    /// The size of a glyph cell in pixels.
+    pub scale_px: f32,
+    /// The maximum width of a line in pixels. Lines wider than this are wrapped
+    /// at word boundaries. When this is `None`, lines only break on newlines.
+    pub max_width_px: Option<f32>,
+    /// The alignment of each line inside the layout box.
+    pub alignment: Alignment,
+    /// The distance between consecutive lines as a multiple of `scale_px`.
+    pub line_spacing: f32,
+    /// Whether to parse color tags in the text. See [`parse_markup`] for the syntax.
+    pub markup: bool,
+}
+
+impl LayoutParams {
+    /// Construct layout parameters for a single left aligned block of text
+    /// without wrapping.
+    pub fn new(scale_px: f32) -> LayoutParams {
+        LayoutParams {
+            scale_px: scale_px,
+            max_width_px: None,
+            alignment: Alignment::Left,
+            line_spacing: 1.0,
+            markup: false,
+        }
+    }
+}
+
+/// A glyph placed by the layout engine. The position is the top left corner of the
+/// glyph's quad in pixels from the top left corner of the layout box, y-axis down.
+#[derive(Copy, Clone, Debug)]
+pub struct PlacedGlyph {
+    pub ch: char,
+    pub x: f32,
+    pub y: f32,
+    pub advance: f32,
+    pub line: usize,
+    /// The color set by markup, or `None` to use the default color for the draw.
+    pub color: Option<[f32; 4]>,
+    /// The opacity of the glyph, which multiplies the alpha of its color.
+    pub alpha: f32,
+    /// How far in pixels the glyph is moved down from its place by an animation.
+    /// The displacement does not count towards the bounds of the text, so animated
+    /// text does not move around on the screen as a whole.
+    pub displacement_y: f32,
+}
+
+/// The size of the viewport that text is drawn into, in pixels.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Viewport {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Viewport {
+    pub fn new(width: u32, height: u32) -> Viewport {
+        Viewport { width: width, height: height }
+    }
+}
+
+/// The result of laying out a string with a font atlas.
+#[derive(Clone, Debug)]
+pub struct TextLayout {
+    pub glyphs: Vec<PlacedGlyph>,
+    pub line_widths: Vec<f32>,
+    pub width: f32,
+    pub height: f32,
+    pub scale_px: f32,
+}
+
+impl TextLayout {
+    #[inline]
+    pub fn glyph_count(&self) -> usize {
+        self.glyphs.len()
+    }
+
+    #[inline]
+    pub fn line_count(&self) -> usize {
+        self.line_widths.len()
+    }
+
+    /// Generate the vertices for drawing the layout as one textured quad per glyph,
+    /// with the top left corner of the layout box at `(start_x, start_y)` in
+    /// normalized device coordinates. The vertex positions and texture coordinates
+    /// are appended to `points` and `texcoords` respectively, two floats per vertex.
+    pub fn append_vertices(
+        &self, atlas: &FontAtlas, start_x: f32, start_y: f32, viewport: Viewport,
+        points: &mut Vec<f32>, texcoords: &mut Vec<f32>) {
+
+        let viewport_width = viewport.width;
+        let viewport_height = viewport.height;
+        for glyph in self.glyphs.iter() {
+            let rect = atlas.glyph_rect(glyph.ch).unwrap();
+            let glyph_width = pixels_to_ndc(rect.quad_width * self.scale_px, viewport_width);
+            let glyph_height = pixels_to_ndc(rect.quad_height * self.scale_px, viewport_height);
+            let cell_width = rect.width;
+            let cell_height = rect.height;
+            let s = rect.x;
+            let t = rect.y + rect.height;
+
+            let x_pos = start_x + pixels_to_ndc(glyph.x, viewport_width);
+            let y_pos = start_y - pixels_to_ndc(glyph.y + glyph.displacement_y, viewport_height);
+
+            points.extend_from_slice(&[
+                x_pos,               y_pos,
+                x_pos,               y_pos - glyph_height,
+                x_pos + glyph_width, y_pos - glyph_height,
+                x_pos + glyph_width, y_pos - glyph_height,
+                x_pos + glyph_width, y_pos,
+                x_pos,               y_pos,
+            ]);
+            texcoords.extend_from_slice(&[
+                s,              1.0 - t + cell_height,
+                s,              1.0 - t,
+                s + cell_width, 1.0 - t,
+                s + cell_width, 1.0 - t,
+                s + cell_width, 1.0 - t + cell_height,
+                s,              1.0 - t + cell_height,
+            ]);
+        }
+    }
+
+    /// Append the color of every vertex of the layout to `colors`, four floats per
+    /// vertex. Glyphs without a color from markup get the color `default`.
+    pub fn append_colors(&self, default: [f32; 4], colors: &mut Vec<f32>) {
+        for glyph in self.glyphs.iter() {
+            let mut color = glyph.color.unwrap_or(default);
+            color[3] *= glyph.alpha;
+            // One color for each of the six vertices of the glyph's quad.
+            for _ in 0..6 {
+                colors.extend_from_slice(&color);
+            }
+        }
+    }
+
+    /// Compute the bounding box of the glyphs in the layout.
+    pub fn bounds(&self) -> TextBounds {
+        if self.glyphs.is_empty() {
+            return TextBounds::new(0.0, 0.0, 0.0, 0.0);
+        }
+
+        let mut min_x = f32::MAX;
+        let mut min_y = f32::MAX;
+        let mut max_x = f32::MIN;
+        let mut max_y = f32::MIN;
+        for glyph in self.glyphs.iter() {
+            min_x = f32::min(min_x, glyph.x);
+            min_y = f32::min(min_y, glyph.y);
+            max_x = f32::max(max_x, glyph.x + glyph.advance);
+            max_y = f32::max(max_y, glyph.y + self.scale_px);
+        }
+
+        TextBounds::new(min_x, min_y, max_x - min_x, max_y - min_y)
+    }
+}
+
+/// Convert a length in pixels to normalized device coordinates along a viewport
+/// `viewport_px` pixels long, which spans two units in normalized device coordinates.
+#[inline]
+pub fn pixels_to_ndc(px: f32, viewport_px: u32) -> f32 {
+    2.0 * px / (viewport_px as f32)
+}
+
+/// The bounding box of a string. The box is measured in pixels from the top left
+/// corner of the layout box, with the y-axis pointing down the screen.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TextBounds {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl TextBounds {
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> TextBounds {
+        TextBounds { x: x, y: y, width: width, height: height }
+    }
+
+    /// The width of the bounding box in normalized device coordinates in a viewport
+    /// that is `viewport_width` pixels wide.
+    #[inline]
+    pub fn width_ndc(&self, viewport_width: u32) -> f32 {
+        pixels_to_ndc(self.width, viewport_width)
+    }
+
+    /// The height of the bounding box in normalized device coordinates in a viewport
+    /// that is `viewport_height` pixels high.
+    #[inline]
+    pub fn height_ndc(&self, viewport_height: u32) -> f32 {
+        pixels_to_ndc(self.height, viewport_height)
+    }
+
+    /// Compute the starting position in normalized device coordinates that places
+    /// the center of the bounding box at the point `(x, y)`.
+    pub fn centered_at(&self, x: f32, y: f32, viewport_width: u32, viewport_height: u32) -> (f32, f32) {
+        let start_x = x - pixels_to_ndc(self.x + 0.5 * self.width, viewport_width);
+        let start_y = y + pixels_to_ndc(self.y + 0.5 * self.height, viewport_height);
+
+        (start_x, start_y)
+    }
+}
+
+/// A user-perceived character: a base glyph followed by any combining marks
+/// drawn on top of it, such as the accent in a decomposed "e\u{301}".
+#[derive(Clone, Debug)]
+struct Cluster {
+    base: char,
+    marks: Vec<char>,
+    color: Option<[f32; 4]>,
+}
+
+impl Cluster {
+    #[inline]
+    fn is_space(&self) -> bool {
+        self.base == ' ' && self.marks.is_empty()
+    }
+}
+
+/// Resolve a grapheme cluster against the font atlas. Combining marks that the 
+/// atlas has no glyph for are dropped, and the whole cluster is dropped when 
+/// its base character cannot be rendered.
+fn resolve_cluster(atlas: &FontAtlas, grapheme: &str, color: Option<[f32; 4]>) -> Option<Cluster> {
+    let mut chars = grapheme.chars();
+    let base = atlas.resolve_glyph(chars.next()?)?;
+    let marks = chars.filter(|&mark| {
+        if atlas.has_glyph(mark) {
+            true
+        } else {
+            if atlas.missing_glyphs.borrow_mut().insert(mark) {
+                warn!("Font atlas has no glyph for the combining mark {:?}. Skipping it.", mark);
+            }
+            false
+        }
+    }).collect();
+
+    Some(Cluster { base: base, marks: marks, color: color })
+}
+
+/// A run of text drawn in one color. A color of `None` means the default color 
+/// for the draw.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Span {
+    pub text: String,
+    pub color: Option<[f32; 4]>,
+}
+
+/// Parse a color in the form `#RRGGBB` or `#RRGGBBAA`.
+fn parse_color(st: &str) -> Option<[f32; 4]> {
+    let hex = st.strip_prefix('#')?;
+    if (hex.len() != 6 && hex.len() != 8) || !hex.is_ascii() {
+        return None;
+    }
+
+    let mut color = [1.0; 4];
+    for i in 0..(hex.len() / 2) {
+        let byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).ok()?;
+        color[i] = (byte as f32) / 255.0;
+    }
+
+    Some(color)
+}
+
+/// Split a string with `[color=#RRGGBB]...[/color]` markup into runs of a single
+/// color. Tags nest, `[[` is a literal `[`, and malformed tags are left as text.
+pub fn parse_markup(text: &str) -> Vec<Span> {
+    let mut spans = vec![];
+    let mut stack: Vec<[f32; 4]> = vec![];
+    let mut current = String::new();
+    let mut rest = text;
+    while let Some(index) = rest.find('[') {
+        current.push_str(&rest[..index]);
+        rest = &rest[index..];
+        if rest.starts_with("[[") {
+            current.push('[');
+            rest = &rest[2..];
+            continue;
+        }
+
+        let tag = match rest.find(']') {
+            Some(end) => &rest[1..end],
+            None => "",
+        };
+        let push_color = match tag.strip_prefix("color=") {
+            Some(value) => {
+                let color = parse_color(value);
+                if color.is_none() {
+                    warn!("Could not parse the color in the tag [{}]. Drawing it as text.", tag);
+                }
+                color
+            }
+            None => None,
+        };
+        if push_color.is_none() && tag != "/color" {
+            // Not a tag, so the bracket is part of the text.
+            current.push('[');
+            rest = &rest[1..];
+            continue;
+        }
+
+        if !current.is_empty() {
+            spans.push(Span { text: current, color: stack.last().copied() });
+            current = String::new();
+        }
+        match push_color {
+            Some(color) => stack.push(color),
+            None => {
+                if stack.pop().is_none() {
+                    warn!("Unmatched [/color] tag in {:?}. Ignoring it.", text);
+                }
+            }
+        }
+        rest = &rest[tag.len() + 2..];
+    }
+    current.push_str(rest);
+    if !current.is_empty() {
+        spans.push(Span { text: current, color: stack.last().copied() });
+    }
+
+    spans
+}
+
+/// A point on the screen, or on a block of text, that text can be attached to.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    /// The position of the anchor inside a box as fractions of the box's width 
+    /// and height, measured from the top left corner.
+    fn fractions(self) -> (f32, f32) {
+        match self {
+            Anchor::TopLeft => (0.0, 0.0),
+            Anchor::Top => (0.5, 0.0),
+            Anchor::TopRight => (1.0, 0.0),
+            Anchor::Left => (0.0, 0.5),
+            Anchor::Center => (0.5, 0.5),
+            Anchor::Right => (1.0, 0.5),
+            Anchor::BottomLeft => (0.0, 1.0),
+            Anchor::Bottom => (0.5, 1.0),
+            Anchor::BottomRight => (1.0, 1.0),
+        }
+    }
+}
+
+/// The placement of a block of text on the screen: the anchor point of its bounding
+/// box is pinned to the same point of the screen, then moved by the offset in pixels.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Placement {
+    pub anchor: Anchor,
+    pub offset_x: f32,
+    pub offset_y: f32,
+}
+
+impl Placement {
+    pub fn new(anchor: Anchor, offset_x: f32, offset_y: f32) -> Placement {
+        Placement { anchor: anchor, offset_x: offset_x, offset_y: offset_y }
+    }
+
+    /// Compute the position in normalized device coordinates of the top left corner 
+    /// of the layout box for text with the bounding box `bounds`.
+    pub fn start_position(&self, bounds: &TextBounds, viewport_width: u32, viewport_height: u32) -> (f32, f32) {
+        let (fraction_x, fraction_y) = self.anchor.fractions();
+        let screen_x = -1.0 + 2.0 * fraction_x;
+        let screen_y = 1.0 - 2.0 * fraction_y;
+        let bounds_x = bounds.x + fraction_x * bounds.width;
+        let bounds_y = bounds.y + fraction_y * bounds.height;
+        let start_x = screen_x + pixels_to_ndc(self.offset_x - bounds_x, viewport_width);
+        let start_y = screen_y - pixels_to_ndc(self.offset_y - bounds_y, viewport_height);
+
+        (start_x, start_y)
+    }
+}
+
+/// A drop shadow drawn beneath a string. The offset and softness are in pixels, 
+/// with the y-axis pointing down the screen.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Shadow {
+    pub offset_x: f32,
+    pub offset_y: f32,
+    pub color: [f32; 4],
+    /// The radius in pixels over which the edge of the shadow fades out. A shadow
+    /// with zero softness has hard edges.
+    pub softness: f32,
+}
+
+/// An outline drawn around a string. The thickness is in pixels.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Outline {
+    pub thickness: f32,
+    pub color: [f32; 4],
+}
+
+/// The effects drawn behind a string to keep it readable over a busy background.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TextEffects {
+    pub shadow: Option<Shadow>,
+    pub outline: Option<Outline>,
+}
+
+/// One silhouette of a string drawn for an effect, offset from the string by
+/// `(offset_x, offset_y)` pixels.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct EffectPass {
+    pub offset_x: f32,
+    pub offset_y: f32,
+    pub color: [f32; 4],
+}
+
+/// The number of copies in the ring of silhouettes that blurs a soft shadow.
+const SHADOW_SAMPLES: usize = 8;
+
+/// Place `count` silhouettes evenly around a circle of radius `radius` centered on
+/// `(center_x, center_y)`.
+fn ring(center_x: f32, center_y: f32, radius: f32, count: usize, color: [f32; 4], passes: &mut Vec<EffectPass>) {
+    for i in 0..count {
+        let angle = 2.0 * std::f32::consts::PI * (i as f32) / (count as f32);
+        passes.push(EffectPass {
+            offset_x: center_x + radius * f32::cos(angle),
+            offset_y: center_y + radius * f32::sin(angle),
+            color: color,
+        });
+    }
+}
+
+impl Default for TextEffects {
+    fn default() -> TextEffects {
+        TextEffects::new()
+    }
+}
+
+impl TextEffects {
+    /// Construct a set of effects that draws the text plainly.
+    pub fn new() -> TextEffects {
+        TextEffects { shadow: None, outline: None }
+    }
+
+    /// Determine whether the text is drawn plainly.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.shadow.is_none() && self.outline.is_none()
+    }
+
+    /// The silhouettes to draw beneath the text, in back to front order: the shadow,
+    /// blurred by a ring of faint copies when soft, and then the outline.
+    pub fn passes(&self) -> Vec<EffectPass> {
+        let mut passes = vec![];
+        if let Some(shadow) = self.shadow {
+            let soft_samples = if shadow.softness > 0.0 { SHADOW_SAMPLES } else { 0 };
+            let outline_count = self.outline.map_or(0, |outline| outline_samples(outline.thickness));
+            let count = 1 + soft_samples + outline_count;
+            let alpha = 1.0 - f32::powf(1.0 - shadow.color[3], 1.0 / (count as f32));
+            let color = [shadow.color[0], shadow.color[1], shadow.color[2], alpha];
+            passes.push(EffectPass { offset_x: shadow.offset_x, offset_y: shadow.offset_y, color: color });
+            ring(shadow.offset_x, shadow.offset_y, shadow.softness, soft_samples, color, &mut passes);
+            if let Some(outline) = self.outline {
+                // Shadow the outlined text, not just the glyphs inside the outline.
+                ring(shadow.offset_x, shadow.offset_y, outline.thickness, outline_count, color, &mut passes);
+            }
+        }
+        if let Some(outline) = self.outline {
+            ring(0.0, 0.0, outline.thickness, outline_samples(outline.thickness), outline.color, &mut passes);
+        }
+
+        passes
+    }
+}
+
+/// The number of copies in the ring of silhouettes that makes an outline. Thicker
+/// outlines need more copies to close the gaps between them.
+fn outline_samples(thickness: f32) -> usize {
+    let samples = f32::ceil(std::f32::consts::PI * thickness) as usize;
+
+    usize::min(usize::max(samples, 8), 32)
+}
+
+/// A line of text after wrapping, before it has been positioned.
+struct Line {
+    clusters: Vec<Cluster>,
+    width: f32,
+}
+
+impl Line {
+    fn new() -> Line {
+        Line { clusters: vec![], width: 0.0 }
+    }
+
+    /// Append a cluster to the end of the line, kerning it against the
+    /// previous cluster.
+    fn push(&mut self, atlas: &FontAtlas, cluster: &Cluster, scale_px: f32) {
+        if let Some(prev) = self.clusters.last() {
+            self.width += atlas.kerning(prev.base, cluster.base) * scale_px;
+        }
+        self.clusters.push(cluster.clone());
+        self.width += advance(atlas, cluster.base, scale_px);
+    }
+}
+
+#[inline]
+fn advance(atlas: &FontAtlas, ch: char, scale_px: f32) -> f32 {
+    atlas.glyph_widths[&ch] * scale_px
+}
+
+/// The position of the top left corner of the glyph `ch` relative to the pen position.
+#[inline]
+fn offset(atlas: &FontAtlas, ch: char, scale_px: f32) -> (f32, f32) {
+    let offset_x = atlas.glyph_rect(ch).map_or(0.0, |rect| rect.offset_x);
+
+    (offset_x * scale_px, atlas.glyph_y_offsets[&ch] * scale_px)
+}
+
+/// Compute the width of a run of clusters, including the kerning between them.
+fn run_width(atlas: &FontAtlas, clusters: &[Cluster], scale_px: f32) -> f32 {
+    let mut width = 0.0;
+    let mut prev: Option<char> = None;
+    for cluster in clusters.iter() {
+        if let Some(prev) = prev {
+            width += atlas.kerning(prev, cluster.base) * scale_px;
+        }
+        width += advance(atlas, cluster.base, scale_px);
+        prev = Some(cluster.base);
+    }
+
+    width
+}
+
+/// Break a paragraph into lines no wider than `max_width`, breaking at spaces
+/// where possible and between clusters inside words that are too long.
+fn wrap_paragraph(atlas: &FontAtlas, paragraph: &[Cluster], scale_px: f32, max_width: Option<f32>, lines: &mut Vec<Line>) {
+    let max_width = match max_width {
+        Some(val) => val,
+        None => {
+            let mut line = Line::new();
+            for cluster in paragraph.iter() {
+                line.push(atlas, cluster, scale_px);
+            }
+            lines.push(line);
+            return;
+        }
+    };
+
+    // Walk the paragraph one word at a time, along with the run of spaces before
+    // it. A run of spaces is a single break point, and it is dropped when the line
+    // breaks there, so a wrapped line never starts with a space.
+    let mut line = Line::new();
+    let mut i = 0;
+    while i < paragraph.len() {
+        let spaces_start = i;
+        while i < paragraph.len() && paragraph[i].is_space() {
+            i += 1;
+        }
+        let word_start = i;
+        while i < paragraph.len() && !paragraph[i].is_space() {
+            i += 1;
+        }
+        let spaces = &paragraph[spaces_start..word_start];
+        let word = &paragraph[word_start..i];
+        if word.is_empty() {
+            // Drop the spaces trailing at the end of the paragraph.
+            break;
+        }
+
+        let width = run_width(atlas, word, scale_px);
+        if !line.clusters.is_empty() {
+            if line.width + run_width(atlas, spaces, scale_px) + width > max_width {
+                lines.push(line);
+                line = Line::new();
+            } else {
+                for cluster in spaces.iter() {
+                    line.push(atlas, cluster, scale_px);
+                }
+            }
+        } else if spaces_start == 0 {
+            // Keep the indentation at the start of the paragraph.
+            for cluster in spaces.iter() {
+                line.push(atlas, cluster, scale_px);
+            }
+        }
+
+        if line.width + width > max_width {
+            // The word does not fit on a line by itself, so break it up.
+            for cluster in word.iter() {
+                let cluster_width = advance(atlas, cluster.base, scale_px);
+                if !line.clusters.is_empty() && line.width + cluster_width > max_width {
+                    lines.push(line);
+                    line = Line::new();
+                }
+                line.push(atlas, cluster, scale_px);
+            }
+        } else {
+            for cluster in word.iter() {
+                line.push(atlas, cluster, scale_px);
+            }
+        }
+    }
+
+    lines.push(line);
+}
+
+/// Lay out a string with a font atlas, one grapheme cluster at a time. Lines
+/// break at newlines and wrap to the maximum width in the parameters, if any.
+pub fn layout(atlas: &FontAtlas, text: &str, params: &LayoutParams) -> TextLayout {
+    let scale_px = params.scale_px;
+    let spans = if params.markup {
+        parse_markup(text)
+    } else {
+        vec![Span { text: String::from(text), color: None }]
+    };
+
+    let mut lines = vec![];
+    let mut paragraph = vec![];
+    for span in spans.iter() {
+        for (i, part) in span.text.split('\n').enumerate() {
+            if i > 0 {
+                wrap_paragraph(atlas, &paragraph, scale_px, params.max_width_px, &mut lines);
+                paragraph.clear();
+            }
+            // Replace or drop any characters the atlas cannot render.
+            paragraph.extend(
+                part.trim_end_matches('\r')
+                    .graphemes(true)
+                    .filter_map(|grapheme| resolve_cluster(atlas, grapheme, span.color))
+            );
+        }
+    }
+    wrap_paragraph(atlas, &paragraph, scale_px, params.max_width_px, &mut lines);
+
+    let widest_line = lines.iter().fold(0_f32, |acc, line| f32::max(acc, line.width));
+    let box_width = match params.max_width_px {
+        Some(max_width) => max_width,
+        None => widest_line,
+    };
+    let line_height = scale_px * params.line_spacing;
+
+    let mut glyphs = vec![];
+    let mut line_widths = vec![];
+    for (i, line) in lines.iter().enumerate() {
+        let mut at_x = match params.alignment {
+            Alignment::Left => 0.0,
+            Alignment::Center => 0.5 * (box_width - line.width),
+            Alignment::Right => box_width - line.width,
+        };
+        let at_y = (i as f32) * line_height;
+        let mut prev: Option<char> = None;
+        for cluster in line.clusters.iter() {
+            let ch = cluster.base;
+            if let Some(prev) = prev {
+                at_x += atlas.kerning(prev, ch) * scale_px;
+            }
+            prev = Some(ch);
+            let (offset_x, offset_y) = offset(atlas, ch, scale_px);
+            glyphs.push(PlacedGlyph {
+                ch: ch,
+                x: at_x + offset_x,
+                y: at_y + offset_y,
+                advance: advance(atlas, ch, scale_px),
+                line: i,
+                color: cluster.color,
+                alpha: 1.0,
+                displacement_y: 0.0,
+            });
+            for &mark in cluster.marks.iter() {
+                let (offset_x, offset_y) = offset(atlas, mark, scale_px);
+                glyphs.push(PlacedGlyph {
+                    ch: mark,
+                    x: at_x + offset_x,
+                    y: at_y + offset_y,
+                    advance: 0.0,
+                    line: i,
+                    color: cluster.color,
+                    alpha: 1.0,
+                    displacement_y: 0.0,
+                });
+            }
+            at_x += advance(atlas, ch, scale_px);
+        }
+        line_widths.push(line.width);
+    }
+
+    let height = ((lines.len() - 1) as f32) * line_height + scale_px;
+
+    TextLayout {
+        glyphs: glyphs,
+        line_widths: line_widths,
+        width: widest_line,
+        height: height,
+        scale_px: scale_px,
+    }
+}
+
+/// Measure the bounding box of a string without building any vertex buffers.
+pub fn measure(atlas: &FontAtlas, text: &str, params: &LayoutParams) -> TextBounds {
+    layout(atlas, text, params).bounds()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::font_atlas::Address;
+    use std::collections::HashMap;
+
+
+    const SCALE_PX: f32 = 40.0;
+    const RED: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
+    const BLUE: [f32; 4] = [0.0, 0.0, 1.0, 1.0];
+
+    /// An atlas of square glyphs that each advance one cell, so every character
+    /// is 40 pixels wide at the test scale.
+    fn test_atlas() -> FontAtlas {
+        let chars = "abcdefgh ";
+        let mut glyph_y_offsets = HashMap::new();
+        let mut glyph_widths = HashMap::new();
+        let mut glyph_coords = HashMap::new();
+        for (i, ch) in chars.chars().enumerate() {
+            glyph_y_offsets.insert(ch, 0.0);
+            glyph_widths.insert(ch, 1.0);
+            glyph_coords.insert(ch, Address { row: i / 4, column: i % 4 });
+        }
+
+        FontAtlas {
+            glyph_y_offsets: glyph_y_offsets,
+            glyph_widths: glyph_widths,
+            glyph_coords: glyph_coords,
+            rows: 3,
+            columns: 4,
+            glyph_rects: HashMap::new(),
+            kerning_pairs: HashMap::new(),
+            fallback_glyph: None,
+            distance_field: None,
+            missing_glyphs: Default::default(),
+        }
+    }
+
+    fn wrapped(max_width_px: f32, alignment: Alignment) -> LayoutParams {
+        let mut params = LayoutParams::new(SCALE_PX);
+        params.max_width_px = Some(max_width_px);
+        params.alignment = alignment;
+
+        params
+    }
+
+    /// The characters on each line of a layout.
+    fn line_text(text_layout: &TextLayout) -> Vec<String> {
+        let mut lines = vec![String::new(); text_layout.line_count()];
+        for glyph in text_layout.glyphs.iter() {
+            lines[glyph.line].push(glyph.ch);
+        }
+
+        lines
+    }
+
+    #[test]
+    fn test_newlines_start_new_lines() {
+        let atlas = test_atlas();
+        let text_layout = layout(&atlas, "ab\ncde\r\n", &LayoutParams::new(SCALE_PX));
+
+        assert_eq!(line_text(&text_layout), vec!["ab", "cde", ""]);
+        assert_eq!(text_layout.line_widths, vec![80.0, 120.0, 0.0]);
+        assert_eq!(text_layout.glyphs[2].x, 0.0);
+        assert_eq!(text_layout.glyphs[2].y, SCALE_PX);
+        assert_eq!(text_layout.height, 3.0 * SCALE_PX);
+    }
+
+    #[test]
+    fn test_wrapping_fills_lines_up_to_the_limit() {
+        let atlas = test_atlas();
+        let fits = layout(&atlas, "ab cd", &wrapped(200.0, Alignment::Left));
+        let overflows = layout(&atlas, "ab cd", &wrapped(199.0, Alignment::Left));
+
+        assert_eq!(line_text(&fits), vec!["ab cd"]);
+        assert_eq!(fits.line_widths, vec![200.0]);
+        assert_eq!(line_text(&overflows), vec!["ab", "cd"]);
+        assert_eq!(overflows.line_widths, vec![80.0, 80.0]);
+    }
+
+    #[test]
+    fn test_repeated_spaces_are_one_break_point() {
+        let atlas = test_atlas();
+        let two_spaces = layout(&atlas, "ab  cd", &wrapped(100.0, Alignment::Left));
+        let three_spaces = layout(&atlas, "ab   cd  ", &wrapped(100.0, Alignment::Left));
+
+        assert_eq!(line_text(&two_spaces), vec!["ab", "cd"]);
+        assert_eq!(two_spaces.line_widths, vec![80.0, 80.0]);
+        assert_eq!(two_spaces.glyphs[2].x, 0.0);
+        assert_eq!(line_text(&three_spaces), vec!["ab", "cd"]);
+        assert_eq!(three_spaces.line_widths, vec![80.0, 80.0]);
+    }
+
+    #[test]
+    fn test_repeated_spaces_inside_a_line_are_kept() {
+        let atlas = test_atlas();
+        let text_layout = layout(&atlas, "a  b", &wrapped(200.0, Alignment::Left));
+
+        assert_eq!(line_text(&text_layout), vec!["a  b"]);
+        assert_eq!(text_layout.line_widths, vec![160.0]);
+    }
+
+    #[test]
+    fn test_words_longer_than_a_line_are_split() {
+        let atlas = test_atlas();
+        let text_layout = layout(&atlas, "a bcdefgh", &wrapped(100.0, Alignment::Left));
+
+        assert_eq!(line_text(&text_layout), vec!["a", "bc", "de", "fg", "h"]);
+        assert!(text_layout.line_widths.iter().all(|&width| width <= 100.0));
+    }
+
+    #[test]
+    fn test_alignment_positions_lines_in_the_layout_box() {
+        let atlas = test_atlas();
+        let left = layout(&atlas, "ab\nabcd", &wrapped(200.0, Alignment::Left));
+        let center = layout(&atlas, "ab\nabcd", &wrapped(200.0, Alignment::Center));
+        let right = layout(&atlas, "ab\nabcd", &wrapped(200.0, Alignment::Right));
+
+        assert_eq!((left.glyphs[0].x, left.glyphs[2].x), (0.0, 0.0));
+        assert_eq!((center.glyphs[0].x, center.glyphs[2].x), (60.0, 20.0));
+        assert_eq!((right.glyphs[0].x, right.glyphs[2].x), (120.0, 40.0));
+    }
+
+    fn span(text: &str, color: Option<[f32; 4]>) -> Span {
+        Span { text: String::from(text), color: color }
+    }
+
+    #[test]
+    fn test_markup_plain_text() {
+        assert_eq!(parse_markup("plain text"), vec![span("plain text", None)]);
+        assert_eq!(parse_markup(""), vec![]);
+    }
+
+    #[test]
+    fn test_markup_colors() {
+        let spans = parse_markup("Press [color=#FF0000]ENTER[/color] now");
+
+        assert_eq!(spans, vec![span("Press ", None), span("ENTER", Some(RED)), span(" now", None)]);
+    }
+
+    #[test]
+    fn test_markup_color_with_alpha() {
+        let spans = parse_markup("[color=#0000FF80]a[/color]");
+
+        assert_eq!(spans.len(), 1);
+        assert_eq!(spans[0].color, Some([0.0, 0.0, 1.0, 128.0 / 255.0]));
+    }
+
+    #[test]
+    fn test_markup_nested_colors_restore_the_enclosing_color() {
+        let spans = parse_markup("[color=#FF0000]a[color=#0000FF]b[/color]c[/color]d");
+
+        assert_eq!(spans, vec![
+            span("a", Some(RED)), span("b", Some(BLUE)), span("c", Some(RED)), span("d", None),
+        ]);
+    }
+
+    #[test]
+    fn test_markup_escaped_bracket() {
+        assert_eq!(parse_markup("[[color=#FF0000]"), vec![span("[color=#FF0000]", None)]);
+        assert_eq!(parse_markup("a[[b"), vec![span("a[b", None)]);
+    }
+
+    #[test]
+    fn test_markup_unknown_and_malformed_tags_are_text() {
+        assert_eq!(parse_markup("[b]bold[/b]"), vec![span("[b]bold[/b]", None)]);
+        assert_eq!(parse_markup("[color=red]a"), vec![span("[color=red]a", None)]);
+        assert_eq!(parse_markup("[color=#FF00]a"), vec![span("[color=#FF00]a", None)]);
+    }
+
+    #[test]
+    fn test_markup_unmatched_closing_tag_is_dropped() {
+        assert_eq!(parse_markup("a[/color]b"), vec![span("a", None), span("b", None)]);
+    }
+
+    #[test]
+    fn test_markup_unclosed_color_runs_to_the_end() {
+        assert_eq!(parse_markup("a[color=#FF0000]b"), vec![span("a", None), span("b", Some(RED))]);
+    }
+
+    #[test]
+    fn test_markup_trailing_tag_without_closing_bracket_is_text() {
+        assert_eq!(parse_markup("a[color=#FF0000"), vec![span("a[color=#FF0000", None)]);
+        assert_eq!(parse_markup("a["), vec![span("a[", None)]);
+    }
+
+    #[test]
+    fn test_measure_empty_string() {
+        let atlas = test_atlas();
+
+        assert_eq!(measure(&atlas, "", &LayoutParams::new(SCALE_PX)), TextBounds::new(0.0, 0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_measure_single_line() {
+        let atlas = test_atlas();
+
+        assert_eq!(measure(&atlas, "abc", &LayoutParams::new(SCALE_PX)), TextBounds::new(0.0, 0.0, 120.0, 40.0));
+    }
+
+    #[test]
+    fn test_measure_multiple_lines() {
+        let atlas = test_atlas();
+        let left = measure(&atlas, "ab\nabcd\na", &LayoutParams::new(SCALE_PX));
+        let right = measure(&atlas, "ab\nabcd\na", &wrapped(200.0, Alignment::Right));
+
+        assert_eq!(left, TextBounds::new(0.0, 0.0, 160.0, 120.0));
+        assert_eq!(right, TextBounds::new(40.0, 0.0, 160.0, 120.0));
+    }
+
+    #[test]
+    fn test_pixels_to_ndc() {
+        // The viewport spans two units of normalized device coordinates.
+        assert_eq!(pixels_to_ndc(400.0, 800), 1.0);
+        assert_eq!(pixels_to_ndc(800.0, 800), 2.0);
+        assert_eq!(text_bounds().width_ndc(800), 0.5);
+        assert_eq!(text_bounds().height_ndc(600), 0.2);
+    }
+
+    /// A 200 by 60 pixel box offset 40 pixels into its layout box.
+    fn text_bounds() -> TextBounds {
+        TextBounds::new(40.0, 0.0, 200.0, 60.0)
+    }
+
+    #[test]
+    fn test_centered_at() {
+        let (start_x, start_y) = text_bounds().centered_at(0.0, 0.0, 800, 600);
+
+        // The center of the box is 140 pixels right of and 30 pixels below the start.
+        assert_eq!((start_x, start_y), (-0.35, 0.1));
+        assert_eq!(text_bounds().centered_at(0.5, -0.5, 800, 600), (0.15, -0.4));
+    }
+
+    #[test]
+    fn test_placement_pins_each_anchor_to_the_screen() {
+        // The box is 0.5 wide and 0.2 high in normalized device coordinates, which
+        // leaves 1.5 and 1.8 of the screen uncovered.
+        let bounds = text_bounds();
+        let anchors = [
+            (Anchor::TopLeft, (-1.0, 1.0)),
+            (Anchor::Top, (-0.25, 1.0)),
+            (Anchor::TopRight, (0.5, 1.0)),
+            (Anchor::Left, (-1.0, 0.1)),
+            (Anchor::Center, (-0.25, 0.1)),
+            (Anchor::Right, (0.5, 0.1)),
+            (Anchor::BottomLeft, (-1.0, -0.8)),
+            (Anchor::Bottom, (-0.25, -0.8)),
+            (Anchor::BottomRight, (0.5, -0.8)),
+        ];
+        for &(anchor, (expected_left, expected_top)) in anchors.iter() {
+            let (start_x, start_y) = Placement::new(anchor, 0.0, 0.0).start_position(&bounds, 800, 600);
+            let box_left = start_x + pixels_to_ndc(bounds.x, 800);
+            let box_top = start_y - pixels_to_ndc(bounds.y, 600);
+
+            assert!(f32::abs(box_left - expected_left) < 1e-5, "{:?}: left {} != {}", anchor, box_left, expected_left);
+            assert!(f32::abs(box_top - expected_top) < 1e-5, "{:?}: top {} != {}", anchor, box_top, expected_top);
+        }
+    }
+
+    #[test]
+    fn test_placement_offset_is_in_pixels() {
+        let bounds = text_bounds();
+        let origin = Placement::new(Anchor::TopLeft, 0.0, 0.0).start_position(&bounds, 800, 600);
+        let moved = Placement::new(Anchor::TopLeft, 80.0, 30.0).start_position(&bounds, 800, 600);
+
+        assert!(f32::abs(moved.0 - origin.0 - 0.2) < 1e-5);
+        assert!(f32::abs(moved.1 - origin.1 + 0.1) < 1e-5);
+    }
+
+    #[test]
+    fn test_layout_applies_kerning_between_clusters() {
+        let mut atlas = test_atlas();
+        atlas.kerning_pairs.insert(('a', 'b'), -0.25);
+        let kerned = layout(&atlas, "abab", &LayoutParams::new(SCALE_PX));
+        let unkerned = layout(&atlas, "ba", &LayoutParams::new(SCALE_PX));
+
+        let xs: Vec<f32> = kerned.glyphs.iter().map(|glyph| glyph.x).collect();
+        assert_eq!(xs, vec![0.0, 30.0, 70.0, 100.0]);
+        assert_eq!(kerned.width, 140.0);
+        assert_eq!(unkerned.glyphs[1].x, 40.0);
+        assert_eq!(measure(&atlas, "ab", &LayoutParams::new(SCALE_PX)).width, 70.0);
+    }
+
+    /// The test atlas with a precomposed "é", a combining acute accent, and a
+    /// character that takes three bytes in UTF-8.
+    fn unicode_atlas() -> FontAtlas {
+        let mut atlas = test_atlas();
+        for (i, &ch) in ['\u{e9}', '\u{301}', '\u{65e5}'].iter().enumerate() {
+            atlas.glyph_y_offsets.insert(ch, 0.0);
+            atlas.glyph_widths.insert(ch, 1.0);
+            atlas.glyph_coords.insert(ch, Address { row: 2, column: i + 1 });
+        }
+
+        atlas
+    }
+
+    fn glyph_xs(text_layout: &TextLayout) -> Vec<f32> {
+        text_layout.glyphs.iter().map(|glyph| glyph.x).collect()
+    }
+
+    #[test]
+    fn test_multibyte_characters_advance_once_each() {
+        let atlas = unicode_atlas();
+        let text_layout = layout(&atlas, "\u{65e5}a\u{65e5}", &LayoutParams::new(SCALE_PX));
+
+        assert_eq!(line_text(&text_layout), vec!["\u{65e5}a\u{65e5}"]);
+        assert_eq!(glyph_xs(&text_layout), vec![0.0, 40.0, 80.0]);
+        assert_eq!(text_layout.width, 120.0);
+    }
+
+    #[test]
+    fn test_combining_mark_does_not_advance() {
+        let atlas = unicode_atlas();
+        let text_layout = layout(&atlas, "e\u{301}b", &LayoutParams::new(SCALE_PX));
+
+        assert_eq!(line_text(&text_layout), vec!["e\u{301}b"]);
+        assert_eq!(glyph_xs(&text_layout), vec![0.0, 0.0, 40.0]);
+        assert_eq!(text_layout.glyphs[1].advance, 0.0);
+        assert_eq!(text_layout.width, 80.0);
+    }
+
+    #[test]
+    fn test_precomposed_and_decomposed_are_the_same_width() {
+        let atlas = unicode_atlas();
+        let precomposed = layout(&atlas, "a\u{e9}b", &LayoutParams::new(SCALE_PX));
+        let decomposed = layout(&atlas, "ae\u{301}b", &LayoutParams::new(SCALE_PX));
+
+        assert_eq!(precomposed.glyph_count(), 3);
+        assert_eq!(decomposed.glyph_count(), 4);
+        assert_eq!(precomposed.line_widths, decomposed.line_widths);
+        assert_eq!(precomposed.glyphs[2].x, decomposed.glyphs[3].x);
+    }
+
+    #[test]
+    fn test_wrapping_counts_graphemes_not_bytes() {
+        let atlas = unicode_atlas();
+        // Three characters of three bytes each fit on a line three cells wide.
+        let text_layout = layout(&atlas, "\u{65e5}\u{65e5}\u{65e5} e\u{301}e\u{301}", &wrapped(120.0, Alignment::Left));
+
+        assert_eq!(line_text(&text_layout), vec!["\u{65e5}\u{65e5}\u{65e5}", "e\u{301}e\u{301}"]);
+        assert_eq!(text_layout.line_widths, vec![120.0, 80.0]);
+    }
+
+    #[test]
+    fn test_splitting_a_long_word_keeps_marks_with_their_base() {
+        let atlas = unicode_atlas();
+        let text_layout = layout(&atlas, "e\u{301}e\u{301}e\u{301}", &wrapped(80.0, Alignment::Left));
+
+        assert_eq!(line_text(&text_layout), vec!["e\u{301}e\u{301}", "e\u{301}"]);
+    }
+
+    #[test]
+    fn test_missing_combining_mark_is_dropped() {
+        let atlas = unicode_atlas();
+        let text_layout = layout(&atlas, "a\u{308}b", &LayoutParams::new(SCALE_PX));
+
+        assert_eq!(line_text(&text_layout), vec!["ab"]);
+        assert!(atlas.missing_glyphs.borrow().contains(&'\u{308}'));
+    }
+}
@@ -0,0 +1,323 @@
+#![allow(dead_code)]
+use crate::text::TextLayout;
+
+use std::f32::consts::PI;
+
+
+/// A time driven effect applied to a laid out string. Every animation is a function
+/// of the time in seconds since it started, so the same string can be animated with
+/// several effects at once, and each effect always looks the same at the same time.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TextAnimation {
+    /// Reveal the string one character at a time at `chars_per_second` characters
+    /// per second. Characters that have not been revealed yet are invisible, but
+    /// keep their place, so the text does not shift while it is typed out.
+    Typewriter { chars_per_second: f32 },
+    /// Fade the string in over `fade_in_seconds`, hold it for `hold_seconds`, then
+    /// fade it out over `fade_out_seconds`. Use an infinite hold to fade the text
+    /// in and keep it on the screen.
+    Fade { fade_in_seconds: f32, hold_seconds: f32, fade_out_seconds: f32 },
+    /// Smoothly pulse the opacity of the string between `min_alpha` and fully opaque
+    /// once every `period_seconds`.
+    Pulse { period_seconds: f32, min_alpha: f32 },
+    /// Blink the string on and off once every `period_seconds`, showing it for the
+    /// fraction `duty` of each period.
+    Blink { period_seconds: f32, duty: f32 },
+    /// Bob each glyph up and down in a wave running along the string. The wave is
+    /// `amplitude_px` pixels high and `wavelength_px` pixels long, and each glyph
+    /// makes one full swing every `period_seconds`.
+    Wave { amplitude_px: f32, wavelength_px: f32, period_seconds: f32 },
+}
+
+/// The position in the cycle of a periodic animation, from 0 at the start of each
+/// period up to 1 at the end of it.
+#[inline]
+fn phase(seconds: f32, period_seconds: f32) -> f32 {
+    if period_seconds > 0.0 {
+        (seconds / period_seconds).rem_euclid(1.0)
+    } else {
+        0.0
+    }
+}
+
+/// Compute the opacity of text fading in, holding, and fading out.
+fn fade_alpha(seconds: f32, fade_in_seconds: f32, hold_seconds: f32, fade_out_seconds: f32) -> f32 {
+    if seconds < fade_in_seconds {
+        return seconds / fade_in_seconds;
+    }
+    let seconds = seconds - fade_in_seconds;
+    if seconds < hold_seconds {
+        return 1.0;
+    }
+    let seconds = seconds - hold_seconds;
+    if seconds < fade_out_seconds {
+        return 1.0 - seconds / fade_out_seconds;
+    }
+
+    0.0
+}
+
+impl TextAnimation {
+    /// Apply the animation to a laid out string, `seconds` seconds after the
+    /// animation started.
+    pub fn apply(&self, text_layout: &mut TextLayout, seconds: f32) {
+        let seconds = f32::max(seconds, 0.0);
+        match *self {
+            TextAnimation::Typewriter { chars_per_second } => {
+                let revealed = f32::floor(seconds * chars_per_second) as usize;
+                let mut chars = 0;
+                for glyph in text_layout.glyphs.iter_mut() {
+                    // Combining marks do not advance the line, and appear
+                    // along with the character they modify.
+                    if glyph.advance > 0.0 {
+                        chars += 1;
+                    }
+                    if chars > revealed {
+                        glyph.alpha = 0.0;
+                    }
+                }
+            }
+            TextAnimation::Fade { fade_in_seconds, hold_seconds, fade_out_seconds } => {
+                let alpha = fade_alpha(seconds, fade_in_seconds, hold_seconds, fade_out_seconds);
+                for glyph in text_layout.glyphs.iter_mut() {
+                    glyph.alpha *= alpha;
+                }
+            }
+            TextAnimation::Pulse { period_seconds, min_alpha } => {
+                let wave = 0.5 + 0.5 * f32::cos(2.0 * PI * phase(seconds, period_seconds));
+                let alpha = min_alpha + (1.0 - min_alpha) * wave;
+                for glyph in text_layout.glyphs.iter_mut() {
+                    glyph.alpha *= alpha;
+                }
+            }
+            TextAnimation::Blink { period_seconds, duty } => {
+                if phase(seconds, period_seconds) >= duty {
+                    for glyph in text_layout.glyphs.iter_mut() {
+                        glyph.alpha = 0.0;
+                    }
+                }
+            }
+            TextAnimation::Wave { amplitude_px, wavelength_px, period_seconds } => {
+                let time_phase = phase(seconds, period_seconds);
+                for glyph in text_layout.glyphs.iter_mut() {
+                    let space_phase = if wavelength_px > 0.0 { glyph.x / wavelength_px } else { 0.0 };
+                    let angle = 2.0 * PI * (time_phase - space_phase);
+                    glyph.displacement_y += 0.5 * amplitude_px * f32::sin(angle);
+                }
+            }
+        }
+    }
+}
+
+/// The time in seconds a typewriter animation at `chars_per_second` characters per
+/// second takes to reveal all of a laid out string.
+pub fn reveal_seconds(text_layout: &TextLayout, chars_per_second: f32) -> f32 {
+    let chars = text_layout.glyphs.iter().filter(|glyph| glyph.advance > 0.0).count();
+
+    (chars as f32) / chars_per_second
+}
+
+/// Undo the animations applied to a laid out string, so the same layout can be
+/// animated again for another time without laying the string out again.
+pub fn reset(text_layout: &mut TextLayout) {
+    for glyph in text_layout.glyphs.iter_mut() {
+        glyph.alpha = 1.0;
+        glyph.displacement_y = 0.0;
+    }
+}
+
+/// Apply a sequence of animations to a laid out string, `seconds` seconds after
+/// the animations started.
+pub fn animate(text_layout: &mut TextLayout, animations: &[TextAnimation], seconds: f32) {
+    for animation in animations.iter() {
+        animation.apply(text_layout, seconds);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text::PlacedGlyph;
+
+
+    const EPSILON: f32 = 1e-4;
+
+    fn assert_f32_eq(result: f32, expected: f32) {
+        assert!(f32::abs(result - expected) < EPSILON, "expected {}, got {}", expected, result);
+    }
+
+    fn glyph(ch: char, x: f32, advance: f32) -> PlacedGlyph {
+        PlacedGlyph {
+            ch: ch,
+            x: x,
+            y: 0.0,
+            advance: advance,
+            line: 0,
+            color: None,
+            alpha: 1.0,
+            displacement_y: 0.0,
+        }
+    }
+
+    /// A layout of "abcd" with a combining mark on the "b".
+    fn test_layout() -> TextLayout {
+        TextLayout {
+            glyphs: vec![
+                glyph('a', 0.0, 10.0),
+                glyph('b', 10.0, 10.0),
+                glyph('\u{301}', 10.0, 0.0),
+                glyph('c', 20.0, 10.0),
+                glyph('d', 30.0, 10.0),
+            ],
+            line_widths: vec![40.0],
+            width: 40.0,
+            height: 10.0,
+            scale_px: 10.0,
+        }
+    }
+
+    fn animated(animation: TextAnimation, seconds: f32) -> TextLayout {
+        let mut text_layout = test_layout();
+        animation.apply(&mut text_layout, seconds);
+
+        text_layout
+    }
+
+    fn alphas(text_layout: &TextLayout) -> Vec<f32> {
+        text_layout.glyphs.iter().map(|glyph| glyph.alpha).collect()
+    }
+
+    fn displacements(text_layout: &TextLayout) -> Vec<f32> {
+        text_layout.glyphs.iter().map(|glyph| glyph.displacement_y).collect()
+    }
+
+    fn visible_count(text_layout: &TextLayout) -> usize {
+        text_layout.glyphs.iter().filter(|glyph| glyph.alpha > 0.0).count()
+    }
+
+    #[test]
+    fn test_typewriter_reveal_count() {
+        let typewriter = TextAnimation::Typewriter { chars_per_second: 2.0 };
+
+        assert_eq!(visible_count(&animated(typewriter, 0.0)), 0);
+        assert_eq!(visible_count(&animated(typewriter, 0.49)), 0);
+        assert_eq!(visible_count(&animated(typewriter, 0.5)), 1);
+        assert_eq!(visible_count(&animated(typewriter, 1.25)), 3);
+        assert_eq!(visible_count(&animated(typewriter, 1.5)), 4);
+        assert_eq!(visible_count(&animated(typewriter, 10.0)), 5);
+    }
+
+    #[test]
+    fn test_typewriter_reveals_combining_marks_with_their_base() {
+        let typewriter = TextAnimation::Typewriter { chars_per_second: 1.0 };
+
+        assert_eq!(alphas(&animated(typewriter, 2.0)), vec![1.0, 1.0, 1.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_fade_alpha() {
+        let fade = TextAnimation::Fade { fade_in_seconds: 1.0, hold_seconds: 2.0, fade_out_seconds: 4.0 };
+        let alpha = |seconds: f32| animated(fade, seconds).glyphs[0].alpha;
+
+        assert_f32_eq(alpha(0.0), 0.0);
+        assert_f32_eq(alpha(0.5), 0.5);
+        assert_f32_eq(alpha(2.0), 1.0);
+        assert_f32_eq(alpha(4.0), 0.75);
+        assert_f32_eq(alpha(5.0), 0.5);
+        assert_f32_eq(alpha(7.0), 0.0);
+        assert_f32_eq(alpha(100.0), 0.0);
+    }
+
+    #[test]
+    fn test_pulse_period() {
+        let pulse = TextAnimation::Pulse { period_seconds: 2.0, min_alpha: 0.2 };
+        let alpha = |seconds: f32| animated(pulse, seconds).glyphs[0].alpha;
+
+        assert_f32_eq(alpha(0.0), 1.0);
+        assert_f32_eq(alpha(0.5), 0.6);
+        assert_f32_eq(alpha(1.0), 0.2);
+        assert_f32_eq(alpha(2.0), 1.0);
+        for &seconds in [0.3, 0.9, 1.7].iter() {
+            assert_f32_eq(alpha(seconds + 2.0), alpha(seconds));
+        }
+    }
+
+    #[test]
+    fn test_blink_duty() {
+        let blink = TextAnimation::Blink { period_seconds: 1.0, duty: 0.6 };
+
+        assert_eq!(visible_count(&animated(blink, 0.5)), 5);
+        assert_eq!(visible_count(&animated(blink, 0.7)), 0);
+        assert_eq!(visible_count(&animated(blink, 1.5)), 5);
+    }
+
+    #[test]
+    fn test_negative_time_is_clamped_to_start() {
+        let animations = [
+            TextAnimation::Typewriter { chars_per_second: 2.0 },
+            TextAnimation::Fade { fade_in_seconds: 1.0, hold_seconds: 2.0, fade_out_seconds: 4.0 },
+            TextAnimation::Pulse { period_seconds: 2.0, min_alpha: 0.2 },
+            TextAnimation::Blink { period_seconds: 1.0, duty: 0.6 },
+            TextAnimation::Wave { amplitude_px: 4.0, wavelength_px: 20.0, period_seconds: 1.0 },
+        ];
+        for animation in animations.iter() {
+            let result = animated(*animation, -3.5);
+            let expected = animated(*animation, 0.0);
+            assert_eq!(alphas(&result), alphas(&expected), "{:?}", animation);
+            assert_eq!(displacements(&result), displacements(&expected), "{:?}", animation);
+        }
+    }
+
+    #[test]
+    fn test_overshooting_time_stays_in_range() {
+        let fade = TextAnimation::Fade { fade_in_seconds: 1.0, hold_seconds: 0.0, fade_out_seconds: 1.0 };
+        let pulse = TextAnimation::Pulse { period_seconds: 2.0, min_alpha: 0.2 };
+        for &seconds in [1.0e3, 1.0e6].iter() {
+            for alpha in alphas(&animated(fade, seconds)).into_iter().chain(alphas(&animated(pulse, seconds))) {
+                assert!((0.0..=1.0).contains(&alpha), "alpha {} out of range at {} seconds", alpha, seconds);
+            }
+        }
+    }
+
+    #[test]
+    fn test_wave_amplitude() {
+        let wave = TextAnimation::Wave { amplitude_px: 4.0, wavelength_px: 40.0, period_seconds: 1.0 };
+        let text_layout = animated(wave, 0.25);
+
+        assert_f32_eq(text_layout.glyphs[0].displacement_y, 2.0);
+        assert_f32_eq(text_layout.glyphs[1].displacement_y, 0.0);
+        assert_f32_eq(text_layout.glyphs[3].displacement_y, -2.0);
+    }
+
+    #[test]
+    fn test_animate_applies_every_animation() {
+        let animations = [
+            TextAnimation::Typewriter { chars_per_second: 1.0 },
+            TextAnimation::Fade { fade_in_seconds: 4.0, hold_seconds: 1.0, fade_out_seconds: 1.0 },
+        ];
+        let mut text_layout = test_layout();
+        animate(&mut text_layout, &animations, 2.0);
+
+        assert_eq!(alphas(&text_layout), vec![0.5, 0.5, 0.5, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_reveal_seconds_counts_characters() {
+        // Four characters, one of them carrying a combining mark.
+        assert_f32_eq(reveal_seconds(&test_layout(), 2.0), 2.0);
+    }
+
+    #[test]
+    fn test_reset_undoes_animations() {
+        let animations = [
+            TextAnimation::Typewriter { chars_per_second: 1.0 },
+            TextAnimation::Wave { amplitude_px: 4.0, wavelength_px: 40.0, period_seconds: 1.0 },
+        ];
+        let mut text_layout = test_layout();
+        animate(&mut text_layout, &animations, 1.25);
+        reset(&mut text_layout);
+
+        assert_eq!(alphas(&text_layout), alphas(&test_layout()));
+        assert_eq!(displacements(&text_layout), displacements(&test_layout()));
+    }
+}
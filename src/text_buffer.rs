@@ -0,0 +1,396 @@
+#![allow(dead_code)]
+use crate::gl;
+use crate::gl::types::{GLfloat, GLintptr, GLsizeiptr, GLuint, GLvoid};
+use crate::gl_help as glh;
+use crate::font_atlas::FontAtlas;
+use crate::text;
+use crate::text::{LayoutParams, Placement, TextEffects, TextLayout, Viewport};
+
+use std::mem;
+use std::ptr;
+
+
+/// The number of vertices in each glyph's quad.
+const VERTICES_PER_GLYPH: usize = 6;
+
+/// The vertex color of text whose color comes entirely from the `text_color` uniform,
+/// for glyphs that markup does not color.
+const WHITE: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+
+
+/// A string of text on the GPU. A text buffer owns its vertex buffers and vertex 
+/// array object, so its contents can be changed as often as needed, for example 
+/// every frame for a timer or a score, without leaking GL objects. The buffers
+/// only grow when the text no longer fits in them.
+pub struct TextBuffer {
+    pub vao: GLuint,
+    pub points_vbo: GLuint,
+    pub texcoords_vbo: GLuint,
+    pub colors_vbo: GLuint,
+    pub silhouettes_vbo: GLuint,
+    capacity: usize,
+    glyph_count: usize,
+    contents: Option<Contents>,
+}
+
+/// The inputs that produced the current contents of a text buffer. If none of
+/// these change, the buffer does not need to be rebuilt.
+#[derive(Clone, Debug, PartialEq)]
+struct Contents {
+    text: String,
+    placement: Placement,
+    effects: TextEffects,
+    viewport_width: u32,
+    viewport_height: u32,
+    params: LayoutParams,
+}
+
+impl TextBuffer {
+    /// Create an empty text buffer with room for `capacity` glyphs, whose vertex
+    /// attributes are bound to the inputs `vp`, `vt`, `vc`, and `vs` of the text
+    /// shader `shader`.
+    pub fn new(shader: GLuint, capacity: usize) -> TextBuffer {
+        let capacity = usize::max(capacity, 1);
+        let (vao, points_vbo, texcoords_vbo, colors_vbo, silhouettes_vbo) = create_text_vertex_array(shader, capacity);
+
+        TextBuffer {
+            vao: vao,
+            points_vbo: points_vbo,
+            texcoords_vbo: texcoords_vbo,
+            colors_vbo: colors_vbo,
+            silhouettes_vbo: silhouettes_vbo,
+            capacity: capacity,
+            glyph_count: 0,
+            contents: None,
+        }
+    }
+
+    /// The number of glyphs the buffer can hold without reallocating.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// The number of vertices to draw.
+    #[inline]
+    pub fn point_count(&self) -> usize {
+        VERTICES_PER_GLYPH * self.glyph_count
+    }
+
+    /// Lay out `st` and upload it to the buffer, placed on the screen by `placement`
+    /// and drawn with the effects `effects`. Nothing is uploaded when the text, placement, 
+    /// effects, layout, and viewport are the same as the last update, so calling this 
+    /// every frame relays out the text only when it changes or the window is resized. 
+    /// Returns whether the buffer contents changed.
+    pub fn update(
+        &mut self, context: &glh::GLState, atlas: &FontAtlas, st: &str,
+        params: &LayoutParams, placement: &Placement, effects: &TextEffects) -> bool {
+
+        let contents = Contents {
+            text: String::from(st),
+            placement: *placement,
+            effects: *effects,
+            viewport_width: context.width,
+            viewport_height: context.height,
+            params: *params,
+        };
+        if self.contents.as_ref() == Some(&contents) {
+            return false;
+        }
+
+        let text_layout = text::layout(atlas, st, params);
+        let mut vertices = Vertices::new();
+        vertices.append(context, atlas, &text_layout, placement, effects, WHITE);
+        let glyph_count = vertices.glyph_count();
+        if glyph_count > self.capacity {
+            self.capacity = glyph_count.next_power_of_two();
+        }
+        upload(self.points_vbo, self.texcoords_vbo, self.colors_vbo, self.silhouettes_vbo, self.capacity, &vertices);
+
+        self.glyph_count = glyph_count;
+        self.contents = Some(contents);
+
+        true
+    }
+
+    /// Draw the text with the currently bound shader program and texture.
+    pub fn draw(&self) {
+        unsafe {
+            gl::BindVertexArray(self.vao);
+            gl::DrawArrays(gl::TRIANGLES, 0, self.point_count() as i32);
+        }
+    }
+}
+
+impl Drop for TextBuffer {
+    fn drop(&mut self) {
+        delete_text_vertex_array(self.vao, self.points_vbo, self.texcoords_vbo, self.colors_vbo, self.silhouettes_vbo);
+    }
+}
+
+/// A batch of strings drawn from the same font atlas. Strings pushed into the batch
+/// are accumulated into one vertex stream, with a color per vertex, and flushed to
+/// the GPU and drawn together in a single draw call. Use one batch per atlas
+/// texture, and flush each batch once per frame.
+pub struct TextBatch {
+    pub vao: GLuint,
+    pub points_vbo: GLuint,
+    pub texcoords_vbo: GLuint,
+    pub colors_vbo: GLuint,
+    pub silhouettes_vbo: GLuint,
+    capacity: usize,
+    vertices: Vertices,
+}
+
+impl TextBatch {
+    /// Create an empty text batch with room on the GPU for `capacity` glyphs, whose 
+    /// vertex attributes are bound to the inputs `vp`, `vt`, `vc`, and `vs` of the 
+    /// text shader `shader`.
+    pub fn new(shader: GLuint, capacity: usize) -> TextBatch {
+        let capacity = usize::max(capacity, 1);
+        let (vao, points_vbo, texcoords_vbo, colors_vbo, silhouettes_vbo) = create_text_vertex_array(shader, capacity);
+
+        TextBatch {
+            vao: vao,
+            points_vbo: points_vbo,
+            texcoords_vbo: texcoords_vbo,
+            colors_vbo: colors_vbo,
+            silhouettes_vbo: silhouettes_vbo,
+            capacity: capacity,
+            vertices: Vertices::new(),
+        }
+    }
+
+    /// The number of glyphs waiting to be drawn.
+    #[inline]
+    pub fn glyph_count(&self) -> usize {
+        self.vertices.glyph_count()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.vertices.points.is_empty()
+    }
+
+    /// Lay out `st` and add it to the batch, placed on the screen by `placement` and
+    /// drawn with the effects `effects`. Glyphs are drawn in the color `color`, except
+    /// where markup gives them another color.
+    pub fn push(
+        &mut self, context: &glh::GLState, atlas: &FontAtlas, st: &str,
+        params: &LayoutParams, placement: &Placement, effects: &TextEffects, color: [f32; 4]) {
+
+        let text_layout = text::layout(atlas, st, params);
+        self.push_layout(context, atlas, &text_layout, placement, effects, color);
+    }
+
+    /// Add a string that has already been laid out to the batch. This is how to draw
+    /// text whose layout has been changed after the fact, for example by an animation.
+    pub fn push_layout(
+        &mut self, context: &glh::GLState, atlas: &FontAtlas, text_layout: &TextLayout,
+        placement: &Placement, effects: &TextEffects, color: [f32; 4]) {
+
+        self.vertices.append(context, atlas, text_layout, placement, effects, color);
+    }
+
+    /// Upload the strings in the batch and draw them all in one draw call with the
+    /// currently bound shader program and texture. This empties the batch.
+    pub fn flush(&mut self) {
+        if self.is_empty() {
+            return;
+        }
+
+        let glyph_count = self.glyph_count();
+        if glyph_count > self.capacity {
+            self.capacity = glyph_count.next_power_of_two();
+        }
+        upload(
+            self.points_vbo, self.texcoords_vbo, self.colors_vbo, self.silhouettes_vbo, self.capacity,
+            &self.vertices
+        );
+        unsafe {
+            gl::BindVertexArray(self.vao);
+            gl::DrawArrays(gl::TRIANGLES, 0, (VERTICES_PER_GLYPH * glyph_count) as i32);
+        }
+
+        self.vertices.clear();
+    }
+}
+
+impl Drop for TextBatch {
+    fn drop(&mut self) {
+        delete_text_vertex_array(self.vao, self.points_vbo, self.texcoords_vbo, self.colors_vbo, self.silhouettes_vbo);
+    }
+}
+
+/// The vertex data for text, with separate streams for the position, texture
+/// coordinates, color, and silhouette flag of each vertex. The silhouette flag is
+/// 1 for the copies of the text drawn for shadows and outlines and 0 for the text.
+struct Vertices {
+    points: Vec<f32>,
+    texcoords: Vec<f32>,
+    colors: Vec<f32>,
+    silhouettes: Vec<f32>,
+}
+
+impl Vertices {
+    fn new() -> Vertices {
+        Vertices { points: vec![], texcoords: vec![], colors: vec![], silhouettes: vec![] }
+    }
+
+    #[inline]
+    fn glyph_count(&self) -> usize {
+        self.points.len() / (2 * VERTICES_PER_GLYPH)
+    }
+
+    /// Append the vertices for a laid out string placed on the screen by `placement`.
+    /// The silhouettes for the effects come first, so they are drawn beneath the text.
+    fn append(
+        &mut self, context: &glh::GLState, atlas: &FontAtlas, text_layout: &TextLayout,
+        placement: &Placement, effects: &TextEffects, color: [f32; 4]) {
+
+        let width = context.width;
+        let height = context.height;
+        let viewport = Viewport::new(width, height);
+        let (start_x, start_y) = placement.start_position(&text_layout.bounds(), width, height);
+        let vertex_count = VERTICES_PER_GLYPH * text_layout.glyph_count();
+        for pass in effects.passes().iter() {
+            let pass_x = start_x + text::pixels_to_ndc(pass.offset_x, width);
+            let pass_y = start_y - text::pixels_to_ndc(pass.offset_y, height);
+            text_layout.append_vertices(
+                atlas, pass_x, pass_y, viewport, &mut self.points, &mut self.texcoords
+            );
+            for glyph in text_layout.glyphs.iter() {
+                let color = [pass.color[0], pass.color[1], pass.color[2], pass.color[3] * glyph.alpha];
+                for _ in 0..VERTICES_PER_GLYPH {
+                    self.colors.extend_from_slice(&color);
+                    self.silhouettes.push(1.0);
+                }
+            }
+        }
+        text_layout.append_vertices(
+            atlas, start_x, start_y, viewport, &mut self.points, &mut self.texcoords
+        );
+        text_layout.append_colors(color, &mut self.colors);
+        for _ in 0..vertex_count {
+            self.silhouettes.push(0.0);
+        }
+    }
+
+    fn clear(&mut self) {
+        self.points.clear();
+        self.texcoords.clear();
+        self.colors.clear();
+        self.silhouettes.clear();
+    }
+}
+
+/// Create the vertex buffers and vertex array object for drawing text, with room
+/// for `capacity` glyphs.
+fn create_text_vertex_array(shader: GLuint, capacity: usize) -> (GLuint, GLuint, GLuint, GLuint, GLuint) {
+    let mut points_vbo = 0;
+    let mut texcoords_vbo = 0;
+    let mut colors_vbo = 0;
+    let mut silhouettes_vbo = 0;
+    unsafe {
+        gl::GenBuffers(1, &mut points_vbo);
+        gl::GenBuffers(1, &mut texcoords_vbo);
+        gl::GenBuffers(1, &mut colors_vbo);
+        gl::GenBuffers(1, &mut silhouettes_vbo);
+    }
+    assert!(points_vbo > 0);
+    assert!(texcoords_vbo > 0);
+    assert!(colors_vbo > 0);
+    assert!(silhouettes_vbo > 0);
+    allocate(points_vbo, 2 * VERTICES_PER_GLYPH * capacity);
+    allocate(texcoords_vbo, 2 * VERTICES_PER_GLYPH * capacity);
+    allocate(colors_vbo, 4 * VERTICES_PER_GLYPH * capacity);
+    allocate(silhouettes_vbo, VERTICES_PER_GLYPH * capacity);
+
+    let vp_loc = unsafe { gl::GetAttribLocation(shader, glh::gl_str("vp").as_ptr()) };
+    assert!(vp_loc > -1);
+    let vp_loc = vp_loc as u32;
+
+    let vt_loc = unsafe { gl::GetAttribLocation(shader, glh::gl_str("vt").as_ptr()) };
+    assert!(vt_loc > -1);
+    let vt_loc = vt_loc as u32;
+
+    let vc_loc = unsafe { gl::GetAttribLocation(shader, glh::gl_str("vc").as_ptr()) };
+    assert!(vc_loc > -1);
+    let vc_loc = vc_loc as u32;
+
+    let vs_loc = unsafe { gl::GetAttribLocation(shader, glh::gl_str("vs").as_ptr()) };
+    assert!(vs_loc > -1);
+    let vs_loc = vs_loc as u32;
+
+    let mut vao = 0;
+    unsafe {
+        gl::GenVertexArrays(1, &mut vao);
+        gl::BindVertexArray(vao);
+        gl::BindBuffer(gl::ARRAY_BUFFER, points_vbo);
+        gl::VertexAttribPointer(vp_loc, 2, gl::FLOAT, gl::FALSE, 0, ptr::null());
+        gl::EnableVertexAttribArray(vp_loc);
+        gl::BindBuffer(gl::ARRAY_BUFFER, texcoords_vbo);
+        gl::VertexAttribPointer(vt_loc, 2, gl::FLOAT, gl::FALSE, 0, ptr::null());
+        gl::EnableVertexAttribArray(vt_loc);
+        gl::BindBuffer(gl::ARRAY_BUFFER, colors_vbo);
+        gl::VertexAttribPointer(vc_loc, 4, gl::FLOAT, gl::FALSE, 0, ptr::null());
+        gl::EnableVertexAttribArray(vc_loc);
+        gl::BindBuffer(gl::ARRAY_BUFFER, silhouettes_vbo);
+        gl::VertexAttribPointer(vs_loc, 1, gl::FLOAT, gl::FALSE, 0, ptr::null());
+        gl::EnableVertexAttribArray(vs_loc);
+    }
+    assert!(vao > 0);
+
+    (vao, points_vbo, texcoords_vbo, colors_vbo, silhouettes_vbo)
+}
+
+fn delete_text_vertex_array(
+    vao: GLuint, points_vbo: GLuint, texcoords_vbo: GLuint, colors_vbo: GLuint, silhouettes_vbo: GLuint) {
+
+    unsafe {
+        gl::DeleteVertexArrays(1, &vao);
+        gl::DeleteBuffers(1, &points_vbo);
+        gl::DeleteBuffers(1, &texcoords_vbo);
+        gl::DeleteBuffers(1, &colors_vbo);
+        gl::DeleteBuffers(1, &silhouettes_vbo);
+    }
+}
+
+/// Upload text vertex data into vertex buffers with room for `capacity` glyphs. The 
+/// old storage is orphaned before writing, so the driver does not have to wait on 
+/// any draw calls still reading from it.
+fn upload(
+    points_vbo: GLuint, texcoords_vbo: GLuint, colors_vbo: GLuint, silhouettes_vbo: GLuint, capacity: usize,
+    vertices: &Vertices) {
+
+    allocate(points_vbo, 2 * VERTICES_PER_GLYPH * capacity);
+    write(points_vbo, &vertices.points);
+    allocate(texcoords_vbo, 2 * VERTICES_PER_GLYPH * capacity);
+    write(texcoords_vbo, &vertices.texcoords);
+    allocate(colors_vbo, 4 * VERTICES_PER_GLYPH * capacity);
+    write(colors_vbo, &vertices.colors);
+    allocate(silhouettes_vbo, VERTICES_PER_GLYPH * capacity);
+    write(silhouettes_vbo, &vertices.silhouettes);
+}
+
+/// Allocate fresh, uninitialized storage for `length` floats in a vertex buffer.
+fn allocate(vbo: GLuint, length: usize) {
+    unsafe {
+        gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+        gl::BufferData(
+            gl::ARRAY_BUFFER, (length * mem::size_of::<GLfloat>()) as GLsizeiptr,
+            ptr::null(), gl::DYNAMIC_DRAW
+        );
+    }
+}
+
+/// Write vertex data to the start of a vertex buffer.
+fn write(vbo: GLuint, data: &[f32]) {
+    unsafe {
+        gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+        gl::BufferSubData(
+            gl::ARRAY_BUFFER, 0 as GLintptr, (data.len() * mem::size_of::<GLfloat>()) as GLsizeiptr,
+            data.as_ptr() as *const GLvoid
+        );
+    }
+}